@@ -0,0 +1,63 @@
+use crate::beams::Stress;
+use crate::config;
+
+// Outputs of a beam under the run's load cases, load history and ply failure criterion, the numbers the scoring,
+// the sweep tables and the plots all work from.
+
+// Fatigue life from the recorded load spectrum when one was given, otherwise the fixed amplitude estimate.
+pub fn get_flight_hours(beam: &impl Stress) -> f64 {
+    let run_config = config::get();
+    let correction = run_config.mean_stress_correction;
+    return match &run_config.load_spectrum {
+        Some(spectrum) => beam.spectrum_flight_hours(spectrum, correction),
+        None => beam.flight_hours(correction),
+    };
+}
+
+// Lowest factor of safety over the load cases.
+pub fn get_factor_of_safety(beam: &impl Stress) -> f64 {
+    return config::get()
+        .load_cases
+        .iter()
+        .map(|case| beam.factor_of_safety(case.temperature, config::get().ply_failure))
+        .fold(f64::INFINITY, f64::min);
+}
+
+// Largest deflection in magnitude over the load cases, keeping its sign.
+pub fn get_deflection(beam: &impl Stress) -> f64 {
+    return config::get()
+        .load_cases
+        .iter()
+        .map(|case| beam.vertical_deflection(case.temperature))
+        .fold(0.0, |worst, deflection| {
+            match deflection.abs() > worst.abs() {
+                true => deflection,
+                false => worst,
+            }
+        });
+}
+
+// Largest tension and compression (psi, compression negative) anywhere in the section over the load cases.
+pub fn get_peak_stresses(beam: &impl Stress) -> (f64, f64) {
+    return config::get()
+        .load_cases
+        .iter()
+        .flat_map(|case| beam.plate_stresses(case.temperature))
+        .fold((0.0, 0.0), |(tension, compression), stress| {
+            (
+                f64::max(tension, stress.tension()),
+                f64::min(compression, stress.compression()),
+            )
+        });
+}
+
+// Lowest first ply failure margins of safety over the load cases, tension then compression.
+pub fn get_ply_margins(beam: &impl Stress) -> [f64; 2] {
+    return config::get()
+        .load_cases
+        .iter()
+        .map(|case| beam.ply_margins(case.temperature, config::get().ply_failure))
+        .fold([f64::INFINITY; 2], |[tension, compression], margins| {
+            [tension.min(margins[0]), compression.min(margins[1])]
+        });
+}
@@ -1,5 +1,11 @@
+use crate::crack::{grow_crack, CrackGrowth, CrackMaterial, CrackType, LoadBlock};
+use crate::fatigue::{LoadSpectrum, MeanStressCorrection};
+use crate::laminate::{
+    Laminate, PlyFailure, CARBON_EPOXY_QUASI_ISOTROPIC, CARBON_EPOXY_SPAR_CAP,
+    GLASS_EPOXY_QUASI_ISOTROPIC,
+};
 use duplicate::duplicate;
-use strum_macros::EnumIter;
+use strum_macros::{EnumIter, EnumString};
 
 //Loads
 const VIN: f64 = 800.0;
@@ -7,14 +13,19 @@ const VOUT: f64 = 1400.0;
 const MDOT: f64 = 30.0;
 const ENG_MASS: f64 = 16000.0;
 const ENG1_LOC: f64 = 501.0;
-const ENG2_LOC: f64 = 879.0;
+// The outboard engine hangs off the spar, so no spar can be shorter than its station.
+pub const ENG2_LOC: f64 = 879.0;
 
 const WEIGHT_FUEL: f64 = 252.0;
 const FORCE_LIFT: f64 = 720.0;
 
+// Weight allowance of a spar (lb), a requirement and the default objective's worth of a unit of factor of safety.
+pub const WEIGHT_MAX: f64 = 78000.0;
+
 // materials
-// [density (lb/in^3), yield strength (psi), elastic modulus (psi), cost ($/lb), SigFb (ksi), A (#), B (#)]
-const STEEL_1018: [f64; 7] = [
+// [density (lb/in^3), yield strength (psi), elastic modulus (psi), cost ($/lb), SigFb (ksi), A (#), B (#),
+//  Paris C (in/cycle), Paris m (#), K_IC (ksi*in^0.5)]
+const STEEL_1018: [f64; 10] = [
     0.284,
     76000.0,
     30000000.0,
@@ -22,8 +33,11 @@ const STEEL_1018: [f64; 7] = [
     105.0,
     97.9684641113648,
     -0.1,
+    3.6e-10,
+    3.0,
+    80.0,
 ];
-const STAINLESS_STEEL_17_4PH: [f64; 7] = [
+const STAINLESS_STEEL_17_4PH: [f64; 10] = [
     0.286,
     165000.0,
     28000000.0,
@@ -31,9 +45,14 @@ const STAINLESS_STEEL_17_4PH: [f64; 7] = [
     257.474868044485,
     257.0,
     -0.095,
+    6.6e-9,
+    2.25,
+    45.0,
+];
+const STEEL_SAE_4340: [f64; 10] = [
+    0.283, 132000.0, 29000000.0, 1.22, 237.0, 238.0, -0.0977, 6.6e-9, 2.25, 50.0,
 ];
-const STEEL_SAE_4340: [f64; 7] = [0.283, 132000.0, 29000000.0, 1.22, 237.0, 238.0, -0.0977];
-const ALUMINUM_7075_T6: [f64; 7] = [
+const ALUMINUM_7075_T6: [f64; 10] = [
     0.102,
     73000.0,
     10400000.0,
@@ -41,9 +60,14 @@ const ALUMINUM_7075_T6: [f64; 7] = [
     108.0,
     192.900038382796,
     -0.143,
+    1.5e-9,
+    3.7,
+    26.0,
+];
+const ALUMINUM_2024_T4: [f64; 10] = [
+    0.100, 47000.0, 10600000.0, 5.35, 91.5, 122.0, -0.102, 8.8e-10, 3.59, 34.0,
 ];
-const ALUMINUM_2024_T4: [f64; 7] = [0.100, 47000.0, 10600000.0, 5.35, 91.5, 122.0, -0.102];
-const ALUMINUM_6061_T6: [f64; 7] = [
+const ALUMINUM_6061_T6: [f64; 10] = [
     0.0975,
     40000.0,
     10000000.0,
@@ -51,10 +75,81 @@ const ALUMINUM_6061_T6: [f64; 7] = [
     85.0,
     101.666666666667,
     -0.107,
+    1.0e-9,
+    3.6,
+    26.0,
 ];
-const TITANIUM_ALLOY_TI_6AL_4V: [f64; 7] = [0.16, 128000.0, 16500000.0, 61.50, 249.0, 274.0, -1.04];
+const TITANIUM_ALLOY_TI_6AL_4V: [f64; 10] = [
+    0.16, 128000.0, 16500000.0, 61.50, 249.0, 274.0, -1.04, 3.7e-10, 3.22, 60.0,
+];
+
+// Temperature curves: [temperature (F), yield strength factor, elastic modulus factor] relative to room temperature.
+pub const ROOM_TEMPERATURE: f64 = 70.0;
 
-#[derive(Copy, Clone, EnumIter)]
+const STEEL_1018_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.10, 1.02],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.95, 0.98],
+    [400.0, 0.88, 0.95],
+    [600.0, 0.80, 0.92],
+];
+const STAINLESS_STEEL_17_4PH_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.08, 1.02],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.94, 0.98],
+    [400.0, 0.88, 0.96],
+    [600.0, 0.84, 0.93],
+];
+const STEEL_SAE_4340_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.08, 1.02],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.95, 0.98],
+    [400.0, 0.90, 0.95],
+    [600.0, 0.84, 0.92],
+];
+const ALUMINUM_7075_T6_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.08, 1.03],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.90, 0.97],
+    [300.0, 0.70, 0.93],
+    [400.0, 0.35, 0.87],
+];
+const ALUMINUM_2024_T4_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.07, 1.03],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.93, 0.97],
+    [300.0, 0.85, 0.92],
+    [400.0, 0.70, 0.86],
+];
+const ALUMINUM_6061_T6_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.06, 1.03],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.92, 0.97],
+    [300.0, 0.80, 0.93],
+    [400.0, 0.60, 0.87],
+];
+const TITANIUM_ALLOY_TI_6AL_4V_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.15, 1.03],
+    [70.0, 1.0, 1.0],
+    [200.0, 0.88, 0.97],
+    [400.0, 0.77, 0.93],
+    [600.0, 0.70, 0.88],
+];
+// Laminates are matrix dominated at temperature, so strength drops well before the metals.
+const CARBON_EPOXY_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.05, 1.02],
+    [70.0, 1.0, 1.0],
+    [180.0, 0.88, 0.97],
+    [250.0, 0.72, 0.92],
+];
+const GLASS_EPOXY_TEMPERATURE: &[[f64; 3]] = &[
+    [-100.0, 1.08, 1.03],
+    [70.0, 1.0, 1.0],
+    [180.0, 0.82, 0.95],
+    [250.0, 0.65, 0.88],
+];
+
+#[derive(Copy, Clone, Debug, EnumIter, EnumString)]
 pub enum Material {
     Steel1018,
     StainlessSteel174PH,
@@ -63,9 +158,47 @@ pub enum Material {
     Aluminum2024T4,
     Aluminum6061T6,
     TitaniumAlloyTi6AL4V,
+    CarbonEpoxyQuasiIsotropic,
+    CarbonEpoxySparCap,
+    GlassEpoxyQuasiIsotropic,
+}
+
+fn get_laminate(material: Material) -> Option<&'static Laminate> {
+    return match material {
+        Material::CarbonEpoxyQuasiIsotropic => Some(&CARBON_EPOXY_QUASI_ISOTROPIC),
+        Material::CarbonEpoxySparCap => Some(&CARBON_EPOXY_SPAR_CAP),
+        Material::GlassEpoxyQuasiIsotropic => Some(&GLASS_EPOXY_QUASI_ISOTROPIC),
+        _ => None,
+    };
+}
+
+pub fn is_laminate(material: Material) -> bool {
+    return get_laminate(material).is_some();
 }
 
-fn get_material(material: Material) -> [f64; 7] {
+// Laminates fill the isotropic slots from classical lamination theory: tensile first ply failure in place of
+// yield, a flat Basquin curve anchored at that strength, and no Paris law.
+fn get_laminate_material(laminate: &Laminate) -> [f64; 10] {
+    let properties = laminate.properties();
+    let strength = properties.tsai_wu[0];
+    return [
+        laminate.density,
+        strength,
+        properties.modulus,
+        laminate.cost,
+        strength / 1000.0,
+        strength / 1000.0,
+        laminate.fatigue_exponent,
+        0.0,
+        0.0,
+        0.0,
+    ];
+}
+
+fn get_material(material: Material) -> [f64; 10] {
+    if let Some(laminate) = get_laminate(material) {
+        return get_laminate_material(laminate);
+    }
     return match material {
         Material::Steel1018 => STEEL_1018,
         Material::StainlessSteel174PH => STAINLESS_STEEL_17_4PH,
@@ -74,13 +207,60 @@ fn get_material(material: Material) -> [f64; 7] {
         Material::Aluminum2024T4 => ALUMINUM_2024_T4,
         Material::Aluminum6061T6 => ALUMINUM_6061_T6,
         Material::TitaniumAlloyTi6AL4V => TITANIUM_ALLOY_TI_6AL_4V,
+        _ => unreachable!(),
     };
 }
 
+fn get_temperature_curve(material: Material) -> &'static [[f64; 3]] {
+    return match material {
+        Material::Steel1018 => STEEL_1018_TEMPERATURE,
+        Material::StainlessSteel174PH => STAINLESS_STEEL_17_4PH_TEMPERATURE,
+        Material::SteelSae4340 => STEEL_SAE_4340_TEMPERATURE,
+        Material::Aluminum7075T6 => ALUMINUM_7075_T6_TEMPERATURE,
+        Material::Aluminum2024T4 => ALUMINUM_2024_T4_TEMPERATURE,
+        Material::Aluminum6061T6 => ALUMINUM_6061_T6_TEMPERATURE,
+        Material::TitaniumAlloyTi6AL4V => TITANIUM_ALLOY_TI_6AL_4V_TEMPERATURE,
+        Material::CarbonEpoxyQuasiIsotropic | Material::CarbonEpoxySparCap => {
+            CARBON_EPOXY_TEMPERATURE
+        }
+        Material::GlassEpoxyQuasiIsotropic => GLASS_EPOXY_TEMPERATURE,
+    };
+}
+
+// Yield strength and modulus factors interpolated to the given temperature (F).
+// Outside the tabulated range the end values are held.
+fn get_temperature_factors(material: Material, temperature: f64) -> [f64; 2] {
+    let curve = get_temperature_curve(material);
+    let mut factors = [curve[0][1], curve[0][2]];
+    for point in curve.windows(2) {
+        let (low, high) = (point[0], point[1]);
+        if temperature >= high[0] {
+            factors = [high[1], high[2]];
+        } else if temperature > low[0] {
+            let t = (temperature - low[0]) / (high[0] - low[0]);
+            factors = [
+                low[1] + t * (high[1] - low[1]),
+                low[2] + t * (high[2] - low[2]),
+            ];
+        }
+    }
+    return factors;
+}
+
+fn get_material_at(material: Material, temperature: f64) -> [f64; 10] {
+    let factors = get_temperature_factors(material, temperature);
+    let mut properties = get_material(material);
+    properties[1] *= factors[0];
+    properties[2] *= factors[1];
+    return properties;
+}
+
 //Beam Phenotype Structs
+// Material is used for the webs (or stem), FlangeMaterial for the flanges. They match for a single material beam.
 #[derive(Copy, Clone)]
 pub struct RectBeam {
     pub Material: Material,
+    pub FlangeMaterial: Material,
     pub Length: f64,
     pub Width: f64,
     pub Height: f64,
@@ -90,6 +270,7 @@ pub struct RectBeam {
 #[derive(Copy, Clone)]
 pub struct TBeam {
     pub Material: Material,
+    pub FlangeMaterial: Material,
     pub Length: f64,
     pub Width: f64,
     pub Height: f64,
@@ -100,6 +281,7 @@ pub struct TBeam {
 #[derive(Copy, Clone)]
 pub struct IBeam {
     pub Material: Material,
+    pub FlangeMaterial: Material,
     pub Length: f64,
     pub Width: f64,
     pub Height: f64,
@@ -107,30 +289,122 @@ pub struct IBeam {
     pub FlangeThickness: f64,
 }
 
-//Getting Beam Area
-trait Area {
-    fn area(&self) -> f64;
+// Any one of the beam types, for populations that mix shapes and for the run's winner.
+#[derive(Copy, Clone)]
+pub enum Beams {
+    R(RectBeam),
+    T(TBeam),
+    I(IBeam),
 }
 
-impl Area for RectBeam {
-    fn area(&self) -> f64 {
-        let inner_rect_area =
-            (self.Width - 2.0 * self.Thickness) * (self.Height - 2.0 * self.Thickness);
-        return (self.Width * self.Height) - inner_rect_area;
+impl Beams {
+    // Short name of the section type, used in file names.
+    pub fn shape(&self) -> &'static str {
+        return match self {
+            Beams::R(_) => "rect",
+            Beams::T(_) => "t",
+            Beams::I(_) => "i",
+        };
     }
 }
 
-impl Area for TBeam {
+// One rectangular plate of a cross-section. (x, y) is the plate centre measured from the top left corner of
+// the section, with y pointing down.
+#[derive(Copy, Clone)]
+pub struct Plate {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub material: Material,
+    pub flange: bool,
+}
+
+impl Plate {
     fn area(&self) -> f64 {
-        return self.Width * self.FlangeThickness
-            + (self.Height - self.FlangeThickness) * self.StemThickness;
+        return self.width * self.height;
     }
 }
 
-impl Area for IBeam {
-    fn area(&self) -> f64 {
-        return 2.0 * self.Width * self.FlangeThickness
-            + self.CenterThickness * (self.Height - 2.0 * self.FlangeThickness);
+// Breaking each section into plates lets every property below handle mixed materials the same way.
+pub trait Plates {
+    fn plates(&self) -> Vec<Plate>;
+}
+
+impl Plates for RectBeam {
+    fn plates(&self) -> Vec<Plate> {
+        let web_height = self.Height - 2.0 * self.Thickness;
+        let flange = |y: f64| Plate {
+            x: self.Width / 2.0,
+            y,
+            width: self.Width,
+            height: self.Thickness,
+            material: self.FlangeMaterial,
+            flange: true,
+        };
+        let web = |x: f64| Plate {
+            x,
+            y: self.Height / 2.0,
+            width: self.Thickness,
+            height: web_height,
+            material: self.Material,
+            flange: false,
+        };
+        return vec![
+            flange(self.Thickness / 2.0),
+            flange(self.Height - self.Thickness / 2.0),
+            web(self.Thickness / 2.0),
+            web(self.Width - self.Thickness / 2.0),
+        ];
+    }
+}
+
+impl Plates for TBeam {
+    fn plates(&self) -> Vec<Plate> {
+        let stem_height = self.Height - self.FlangeThickness;
+        return vec![
+            Plate {
+                x: self.Width / 2.0,
+                y: self.FlangeThickness / 2.0,
+                width: self.Width,
+                height: self.FlangeThickness,
+                material: self.FlangeMaterial,
+                flange: true,
+            },
+            Plate {
+                x: self.Width / 2.0,
+                y: self.FlangeThickness + stem_height / 2.0,
+                width: self.StemThickness,
+                height: stem_height,
+                material: self.Material,
+                flange: false,
+            },
+        ];
+    }
+}
+
+impl Plates for IBeam {
+    fn plates(&self) -> Vec<Plate> {
+        let flange = |y: f64| Plate {
+            x: self.Width / 2.0,
+            y,
+            width: self.Width,
+            height: self.FlangeThickness,
+            material: self.FlangeMaterial,
+            flange: true,
+        };
+        return vec![
+            flange(self.FlangeThickness / 2.0),
+            flange(self.Height - self.FlangeThickness / 2.0),
+            Plate {
+                x: self.Width / 2.0,
+                y: self.Height / 2.0,
+                width: self.CenterThickness,
+                height: self.Height - 2.0 * self.FlangeThickness,
+                material: self.Material,
+                flange: false,
+            },
+        ];
     }
 }
 
@@ -142,8 +416,11 @@ pub trait Weight {
 #[duplicate(beam_type; [RectBeam] ; [TBeam] ; [IBeam])]
 impl Weight for beam_type {
     fn weight(&self) -> f64 {
-        let volume = self.area() * self.Length;
-        return volume * get_material(self.Material)[0];
+        return self
+            .plates()
+            .iter()
+            .map(|plate| plate.area() * self.Length * get_material(plate.material)[0])
+            .sum();
     }
 }
 
@@ -155,185 +432,411 @@ pub trait Cost {
 #[duplicate(beam_type; [RectBeam] ; [TBeam] ; [IBeam])]
 impl Cost for beam_type {
     fn cost(&self) -> f64 {
-        let weight = self.weight();
-        return weight * get_material(self.Material)[3];
+        return self
+            .plates()
+            .iter()
+            .map(|plate| {
+                let material = get_material(plate.material);
+                plate.area() * self.Length * material[0] * material[3]
+            })
+            .sum();
     }
 }
 
-// 2nd Moment of Area about x-axis.
-trait Ix {
-    fn ix(&self) -> f64;
+// Transformed section: plates are scaled by their modulus relative to Material, so Ix and Iy are in units of
+// the reference modulus and the centroid is the modulus weighted one.
+pub struct Section {
+    pub modulus: f64,
+    pub x_bend: f64,
+    pub y_bend: f64,
+    pub ix: f64,
+    pub iy: f64,
 }
 
-impl Ix for RectBeam {
-    fn ix(&self) -> f64 {
-        let ix_big = self.Width * f64::powf(self.Height, 3.0) / 12.0;
-        let ix_small = (self.Width - 2.0 * self.Thickness)
-            * f64::powf(self.Height - 2.0 * self.Thickness, 3.0)
-            / 12.0;
-        return ix_big - ix_small;
-    }
+fn modular_ratio(plate: &Plate, reference: Material, temperature: f64) -> f64 {
+    return get_material_at(plate.material, temperature)[2]
+        / get_material_at(reference, temperature)[2];
 }
 
-impl Ix for TBeam {
-    fn ix(&self) -> f64 {
-        let ix_stem =
-            self.StemThickness * f64::powf(self.Height - self.FlangeThickness, 3.0) / 12.0;
-        let ix_flange = self.Width * f64::powf(self.FlangeThickness, 3.0) / 12.0;
-        let stem_displacement =
-            self.y_bend() + (self.Height - self.FlangeThickness) / 2.0 + self.FlangeThickness;
-        let flange_displacement = self.FlangeThickness / 2.0 + self.y_bend();
-        let stem_displacement_ix = f64::powf(stem_displacement, 2.0)
-            * (self.Height - self.FlangeThickness)
-            * self.StemThickness;
-        let flange_displacement_ix =
-            f64::powf(flange_displacement, 2.0) * self.Width * self.FlangeThickness;
-        return ix_stem + ix_flange + stem_displacement_ix + flange_displacement_ix;
+fn transformed_section(plates: &[Plate], reference: Material, temperature: f64) -> Section {
+    let mut weighted_area = 0.0;
+    let mut moment_x = 0.0;
+    let mut moment_y = 0.0;
+    for plate in plates {
+        let area = modular_ratio(plate, reference, temperature) * plate.area();
+        weighted_area += area;
+        moment_x += area * plate.x;
+        moment_y += area * plate.y;
     }
-}
-
-impl Ix for IBeam {
-    fn ix(&self) -> f64 {
-        let ix_small_rectangle = (self.Width - self.CenterThickness) / 2.0
-            * f64::powf(self.Height - 2.0 * self.FlangeThickness, 3.0)
-            / 12.0;
-        let ix_large_rectangle = (self.Width) * f64::powf(self.Height, 3.0) / 12.0;
-        return ix_large_rectangle - 2.0 * ix_small_rectangle;
+    let x_bend = moment_x / weighted_area;
+    let y_bend = moment_y / weighted_area;
+
+    let mut ix = 0.0;
+    let mut iy = 0.0;
+    for plate in plates {
+        let ratio = modular_ratio(plate, reference, temperature);
+        ix += ratio
+            * (plate.width * f64::powf(plate.height, 3.0) / 12.0
+                + plate.area() * f64::powf(plate.y - y_bend, 2.0));
+        iy += ratio
+            * (plate.height * f64::powf(plate.width, 3.0) / 12.0
+                + plate.area() * f64::powf(plate.x - x_bend, 2.0));
     }
-}
 
-// Handle 2nd moment of area about y axis.
-trait Iy {
-    fn iy(&self) -> f64;
+    return Section {
+        modulus: get_material_at(reference, temperature)[2],
+        x_bend,
+        y_bend,
+        ix,
+        iy,
+    };
 }
 
-impl Iy for RectBeam {
-    fn iy(&self) -> f64 {
-        let iy_big = self.Height * f64::powf(self.Width, 3.0) / 12.0;
-        let iy_small = (self.Height - 2.0 * self.Thickness)
-            * f64::powf(self.Width - 2.0 * self.Thickness, 3.0)
-            / 12.0;
-        return iy_big - iy_small;
-    }
+pub trait TransformedSection {
+    fn section(&self, temperature: f64) -> Section;
 }
 
-impl Iy for TBeam {
-    fn iy(&self) -> f64 {
-        let iy_flange = f64::powf(self.Width, 3.0) * self.FlangeThickness / 12.0;
-        let iy_stem =
-            f64::powf(self.StemThickness, 3.0) * (self.Height - self.FlangeThickness) / 12.0;
-        return iy_flange + iy_stem;
+#[duplicate(beam_type; [RectBeam] ; [TBeam] ; [IBeam])]
+impl TransformedSection for beam_type {
+    fn section(&self, temperature: f64) -> Section {
+        return transformed_section(&self.plates(), self.Material, temperature);
     }
 }
 
-impl Iy for IBeam {
-    fn iy(&self) -> f64 {
-        let iy_flange = self.FlangeThickness * f64::powf(self.Width, 3.0) / 12.0;
-        let iy_stem = (self.Height - 2.0 * self.FlangeThickness)
-            * f64::powf(self.CenterThickness, 3.0)
-            / 12.0;
-        return iy_stem + 2.0 * iy_flange;
-    }
+// Bending stresses (psi) at the extreme fibres of one plate, positive in tension: vertical bending at its top and
+// bottom edges, horizontal bending at its left and right edges.
+#[derive(Copy, Clone)]
+pub struct PlateStress {
+    pub plate: Plate,
+    pub vertical: [f64; 2],
+    pub horizontal: [f64; 2],
 }
 
-//Implement X Bend for beams
-
-trait XBend {
-    fn x_bend(&self) -> f64;
-}
+impl PlateStress {
+    // Largest tension over the plate's corners, negative when the whole plate is in compression.
+    pub fn tension(&self) -> f64 {
+        return self.vertical[0].max(self.vertical[1]) + self.horizontal[0].max(self.horizontal[1]);
+    }
 
-#[duplicate(beam_type; [RectBeam] ; [TBeam] ; [IBeam])]
-impl XBend for beam_type {
-    fn x_bend(&self) -> f64 {
-        return self.Width / 2.0;
+    // Largest compression over the plate's corners as a negative stress, positive when the whole plate is in
+    // tension.
+    pub fn compression(&self) -> f64 {
+        return self.vertical[0].min(self.vertical[1]) + self.horizontal[0].min(self.horizontal[1]);
     }
 }
 
-//Implement Y bend for beams
-trait YBend {
-    fn y_bend(&self) -> f64;
+// Cycles to failure from the Basquin curve after mean stress correction. Stresses in ksi.
+fn basquin_cycles(
+    material: [f64; 10],
+    amplitude: f64,
+    mean: f64,
+    correction: MeanStressCorrection,
+) -> f64 {
+    let equivalent =
+        correction.equivalent_amplitude(amplitude, mean, material[4], material[1] / 1000.0);
+    return match equivalent {
+        Some(equivalent) => f64::powf(equivalent / material[5], 1.0 / material[6]),
+        None => 0.0,
+    };
 }
 
-#[duplicate(beam_type; [RectBeam]; [IBeam])]
-impl YBend for beam_type {
-    fn y_bend(&self) -> f64 {
-        return -self.Height / 2.0;
-    }
+//Handle Stress:
+pub trait Stress {
+    fn plate_stresses(&self, temperature: f64) -> Vec<PlateStress>;
+    fn factor_of_safety(&self, temperature: f64, ply_failure: PlyFailure) -> f64;
+    fn flight_hours(&self, correction: MeanStressCorrection) -> f64;
+    fn spectrum_flight_hours(
+        &self,
+        spectrum: &LoadSpectrum,
+        correction: MeanStressCorrection,
+    ) -> f64;
+    fn vertical_deflection(&self, temperature: f64) -> f64;
+    // Lowest first ply failure margins of safety (factor of safety less one) of the laminate plates, in tension and
+    // in compression. Infinite without laminate plates.
+    fn ply_margins(&self, temperature: f64, ply_failure: PlyFailure) -> [f64; 2];
 }
 
-impl YBend for TBeam {
-    fn y_bend(&self) -> f64 {
-        let weighted_stem = (self.FlangeThickness + (self.Height - self.FlangeThickness) / 2.0)
-            * self.StemThickness
-            * (self.Height - self.FlangeThickness);
-        let weighted_flange = (self.FlangeThickness / 2.0) * (self.FlangeThickness * self.Width);
-        return -(weighted_stem + weighted_flange) / self.area();
-    }
+// First ply failure factors of safety of a laminate plate, in tension at its most stretched corner and in compression
+// at its most compressed one, each against its own strength. Infinite on a side with no stress.
+fn first_ply_factors(
+    laminate: &Laminate,
+    stress: &PlateStress,
+    temperature: f64,
+    ply_failure: PlyFailure,
+) -> [f64; 2] {
+    let factor = get_temperature_factors(stress.plate.material, temperature)[0];
+    let check = |stress: f64| match stress == 0.0 {
+        true => f64::INFINITY,
+        false => laminate.first_ply_failure(stress, ply_failure) * factor / stress.abs(),
+    };
+    return [
+        check(stress.tension().max(0.0)),
+        check(stress.compression().min(0.0)),
+    ];
 }
 
-//Handle Stress:
-pub trait Stress {
-    fn stress_vert(&self) -> f64;
-    fn stress_horz(&self) -> f64;
-    fn total_stress(&self) -> f64;
-    fn factor_of_safety(&self) -> f64;
-    fn flight_hours(&self) -> f64;
-    fn vertical_deflection(&self) -> f64;
+// Plate with the largest stress.
+fn governing(stresses: Vec<PlateStress>, stress: impl Fn(&PlateStress) -> f64) -> PlateStress {
+    let mut worst = stresses[0];
+    for candidate in stresses {
+        if stress(&candidate) > stress(&worst) {
+            worst = candidate;
+        }
+    }
+    return worst;
 }
 
 #[duplicate(beam_type; [RectBeam] ; [TBeam] ; [IBeam])]
 impl Stress for beam_type {
-    fn stress_vert(&self) -> f64 {
+    fn plate_stresses(&self, temperature: f64) -> Vec<PlateStress> {
         let m_eng1 = -ENG1_LOC * ENG_MASS;
         let m_eng2 = -ENG2_LOC * ENG_MASS;
 
         let m_lift = (FORCE_LIFT * self.Length) / 2.0 * self.Length / (3.0);
         let m_fuel = -(WEIGHT_FUEL * self.Length) / 2.0 * self.Length / 3.0;
         let m_weight = -(self.weight()) * self.Length / 2.0;
+        let moment_vert = m_eng1 + m_eng2 + m_lift + m_fuel + m_weight;
 
-        return -(m_eng1 + m_eng2 + m_lift + m_fuel + m_weight) * self.y_bend() / self.ix();
-    }
-    fn stress_horz(&self) -> f64 {
         let thrust_eng = (VOUT - VIN) * MDOT;
-
-        let m_eng1 = thrust_eng * ENG1_LOC;
-        let m_eng2 = thrust_eng * ENG2_LOC;
-
-        return (m_eng1 + m_eng2) * self.x_bend() / self.iy();
+        let moment_horz = thrust_eng * ENG1_LOC + thrust_eng * ENG2_LOC;
+
+        // Section y points down, so a positive vertical moment puts the fibres below the neutral axis in tension.
+        let section = self.section(temperature);
+        return self
+            .plates()
+            .iter()
+            .map(|plate| {
+                let ratio = modular_ratio(plate, self.Material, temperature);
+                let vertical = |y: f64| ratio * moment_vert * (y - section.y_bend) / section.ix;
+                let horizontal = |x: f64| ratio * moment_horz * (x - section.x_bend) / section.iy;
+                PlateStress {
+                    plate: *plate,
+                    vertical: [
+                        vertical(plate.y - plate.height / 2.0),
+                        vertical(plate.y + plate.height / 2.0),
+                    ],
+                    horizontal: [
+                        horizontal(plate.x - plate.width / 2.0),
+                        horizontal(plate.x + plate.width / 2.0),
+                    ],
+                }
+            })
+            .collect();
     }
-    fn total_stress(&self) -> f64 {
-        return self.stress_vert() + self.stress_horz();
+    // Each plate is checked against its own material, the lowest factor of safety governs.
+    fn factor_of_safety(&self, temperature: f64, ply_failure: PlyFailure) -> f64 {
+        return self
+            .plate_stresses(temperature)
+            .iter()
+            .map(|stress| {
+                let material = stress.plate.material;
+                match get_laminate(material) {
+                    Some(laminate) => {
+                        let [tension, compression] =
+                            first_ply_factors(laminate, stress, temperature, ply_failure);
+                        f64::min(tension, compression)
+                    }
+                    None => {
+                        get_material_at(material, temperature)[1]
+                            / f64::max(stress.tension(), -stress.compression())
+                    }
+                }
+            })
+            .fold(f64::INFINITY, f64::min);
     }
-    fn factor_of_safety(&self) -> f64 {
-        let yield_strength = get_material(self.Material)[1];
-        return (yield_strength / self.total_stress());
+    fn flight_hours(&self, correction: MeanStressCorrection) -> f64 {
+        return self
+            .plate_stresses(ROOM_TEMPERATURE)
+            .iter()
+            .flat_map(|stress| {
+                let material = get_material(stress.plate.material);
+                // Each edge of the plate with its own sign, so the mean stress correction credits compression.
+                stress.vertical.map(|relevant_stress| {
+                    let amplitude = relevant_stress / 2.0;
+                    let cycles = basquin_cycles(
+                        material,
+                        2.33 * amplitude / 1000.0,
+                        2.33 * relevant_stress / 1000.0,
+                        correction,
+                    );
+                    cycles / 27.0
+                })
+            })
+            .fold(f64::INFINITY, f64::min);
     }
-    fn flight_hours(&self) -> f64 {
-        let material = get_material(self.Material);
-        let g4 = material[4];
-        let h4 = material[5];
-        let i4 = material[6];
-        let relevant_stress = self.stress_vert();
-        let amplitude = relevant_stress / 2.0;
-        let numerator = 2.33 * amplitude / 1000.0;
-        let denominator = (1.0 - (2.33 * relevant_stress / 1000.0) / g4) * h4;
-        return f64::powf(numerator / denominator, 1.0 / i4) / 27.0;
+    fn spectrum_flight_hours(
+        &self,
+        spectrum: &LoadSpectrum,
+        correction: MeanStressCorrection,
+    ) -> f64 {
+        // Bending stress scales linearly with n_z, so each cycle is the 1g stress times its load factor.
+        return self
+            .plate_stresses(ROOM_TEMPERATURE)
+            .iter()
+            .flat_map(|stress| {
+                let material = get_material(stress.plate.material);
+                stress.vertical.map(|vertical| {
+                    let stress_1g = vertical / 1000.0;
+                    let damage: f64 = spectrum
+                        .cycles
+                        .iter()
+                        .map(|cycle| {
+                            let amplitude = stress_1g * cycle.range / 2.0;
+                            let mean = stress_1g * cycle.mean;
+                            cycle.count / basquin_cycles(material, amplitude, mean, correction)
+                        })
+                        .sum();
+                    spectrum.duration_hours / damage
+                })
+            })
+            .fold(f64::INFINITY, f64::min);
     }
-    fn vertical_deflection(&self) -> f64 {
-        let material = get_material(self.Material);
+    fn vertical_deflection(&self, temperature: f64) -> f64 {
+        let section = self.section(temperature);
+        let stiffness = section.modulus * section.ix;
         let length_tofour = f64::powf(self.Length, 4.0);
-        let ix = self.ix();
-        let def_weight = -(self.weight() / self.Length) * length_tofour / 8.0 / material[2] / ix;
-        let def_fuel = -WEIGHT_FUEL * length_tofour / 30.0 / material[2] / ix;
-        let def_lift = FORCE_LIFT * length_tofour / 30.0 / material[2] / ix;
-        let def_eng1 = -ENG_MASS * f64::powf(ENG1_LOC, 2.0) * (3.0 * self.Length - ENG1_LOC)
-            / 6.0
-            / material[2]
-            / ix;
-        let def_eng2 = -ENG_MASS * f64::powf(ENG2_LOC, 2.0) * (3.0 * self.Length - ENG2_LOC)
-            / 6.0
-            / material[2]
-            / ix;
+        let def_weight = -(self.weight() / self.Length) * length_tofour / 8.0 / stiffness;
+        let def_fuel = -WEIGHT_FUEL * length_tofour / 30.0 / stiffness;
+        let def_lift = FORCE_LIFT * length_tofour / 30.0 / stiffness;
+        let def_eng1 =
+            -ENG_MASS * f64::powf(ENG1_LOC, 2.0) * (3.0 * self.Length - ENG1_LOC) / 6.0 / stiffness;
+        let def_eng2 =
+            -ENG_MASS * f64::powf(ENG2_LOC, 2.0) * (3.0 * self.Length - ENG2_LOC) / 6.0 / stiffness;
         return def_fuel + def_lift + def_eng1 + def_eng2 + def_weight;
     }
+    fn ply_margins(&self, temperature: f64, ply_failure: PlyFailure) -> [f64; 2] {
+        return self
+            .plate_stresses(temperature)
+            .iter()
+            .filter_map(|stress| {
+                get_laminate(stress.plate.material)
+                    .map(|laminate| first_ply_factors(laminate, stress, temperature, ply_failure))
+            })
+            .fold([f64::INFINITY; 2], |[tension, compression], factors| {
+                [
+                    tension.min(factors[0] - 1.0),
+                    compression.min(factors[1] - 1.0),
+                ]
+            });
+    }
+}
+
+// Damage tolerance: grow a crack in the flange under the same loading used for fatigue life.
+// Fails for laminate flanges and for a detectable crack the flange cannot survive.
+pub trait DamageTolerance {
+    fn crack_growth(
+        &self,
+        crack: CrackType,
+        detectable_length: f64,
+        spectrum: Option<&LoadSpectrum>,
+    ) -> Result<CrackGrowth, String>;
+}
+
+#[duplicate(beam_type; [RectBeam] ; [TBeam] ; [IBeam])]
+impl DamageTolerance for beam_type {
+    fn crack_growth(
+        &self,
+        crack: CrackType,
+        detectable_length: f64,
+        spectrum: Option<&LoadSpectrum>,
+    ) -> Result<CrackGrowth, String> {
+        // Laminates fail by delamination rather than a growing crack, so the Paris law does not apply.
+        if is_laminate(self.FlangeMaterial) {
+            return Err(String::from(
+                "Paris law crack growth does not apply to laminates",
+            ));
+        }
+        let flanges = self
+            .plate_stresses(ROOM_TEMPERATURE)
+            .into_iter()
+            .filter(|stress| stress.plate.flange)
+            .collect();
+        // Cracks grow from the tensile edge, so the flange with the most tension governs.
+        let flange = governing(flanges, |stress| stress.vertical[0].max(stress.vertical[1]));
+        let material = get_material(self.FlangeMaterial);
+        let crack_material = CrackMaterial {
+            paris_c: material[7],
+            paris_m: material[8],
+            fracture_toughness: material[9],
+        };
+        let stress_1g = flange.vertical[0].max(flange.vertical[1]) / 1000.0;
+        let loading = match spectrum {
+            Some(spectrum) => LoadBlock {
+                ranges: spectrum
+                    .cycles
+                    .iter()
+                    .map(|cycle| (stress_1g * cycle.range, cycle.count))
+                    .collect(),
+                peak_stress: spectrum
+                    .cycles
+                    .iter()
+                    .map(|cycle| stress_1g * (cycle.mean + cycle.range / 2.0))
+                    .fold(0.0, f64::max),
+                hours: spectrum.duration_hours,
+            },
+            // Same fixed amplitude assumption as flight_hours: 27 cycles per hour up to 2.33g.
+            None => LoadBlock {
+                ranges: vec![(2.33 * stress_1g, 27.0)],
+                peak_stress: 2.33 * stress_1g,
+                hours: 1.0,
+            },
+        };
+        return grow_crack(
+            crack,
+            crack_material,
+            flange.plate.width,
+            &loading,
+            detectable_length,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A T with its flange over the neutral axis and the stem reaching well below it, so the tensile peak at the foot
+    // of the stem is larger than the compressive peak at the flange.
+    fn laminate_tbeam() -> TBeam {
+        return TBeam {
+            Material: Material::CarbonEpoxySparCap,
+            FlangeMaterial: Material::CarbonEpoxySparCap,
+            Length: 1257.0,
+            Height: 34.0,
+            Width: 30.0,
+            StemThickness: 2.0,
+            FlangeThickness: 2.0,
+        };
+    }
+
+    #[test]
+    fn laminate_plates_are_checked_in_tension_and_compression() {
+        let beam = laminate_tbeam();
+        let factor = get_temperature_factors(Material::CarbonEpoxySparCap, ROOM_TEMPERATURE)[0];
+        let stresses = beam.plate_stresses(ROOM_TEMPERATURE);
+        for criterion in [PlyFailure::TsaiWu, PlyFailure::MaxStress] {
+            let properties = get_laminate(Material::CarbonEpoxySparCap)
+                .unwrap()
+                .properties();
+            let strengths = match criterion {
+                PlyFailure::TsaiWu => properties.tsai_wu,
+                PlyFailure::MaxStress => properties.max_stress,
+            };
+            assert!(strengths[0] != strengths[1]);
+            let expected = stresses
+                .iter()
+                .map(|stress| {
+                    f64::min(
+                        strengths[0] * factor / stress.tension().max(0.0),
+                        strengths[1] * factor / (-stress.compression()).max(0.0),
+                    )
+                })
+                .fold(f64::INFINITY, f64::min);
+            let fos = beam.factor_of_safety(ROOM_TEMPERATURE, criterion);
+            assert!((fos - expected).abs() < 1.0e-9 * fos);
+        }
+        // Both signs are present, with the compressive peak at the top flange.
+        assert!(stresses.iter().any(|stress| stress.tension() > 0.0));
+        assert!(stresses[0].compression() < 0.0);
+    }
 }
@@ -0,0 +1,320 @@
+use crate::beams::{Beams, IBeam, Material, RectBeam, TBeam};
+use crate::config::Config;
+use crate::constraints;
+use crate::fitness::BeamFitness;
+use crate::ga::Genes;
+use crate::history::GenerationStats;
+use crate::mutation;
+use crate::rng;
+use crate::stopping::EarlyStopper;
+use rand::Rng;
+use rsgenetic::pheno::Phenotype;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// Checkpoints fall on these generations, and the generator is reseeded there whether or not they are saved so a
+// resumed run matches one that was never stopped.
+pub const CHECKPOINT_GENERATIONS: u64 = 100;
+
+// Text form of a phenotype, one line per individual. Floats print with Rust's shortest round trip format, so a
+// reloaded population is bit for bit the one that was saved.
+pub trait Record: Sized {
+    fn record(&self) -> String;
+    fn from_record(line: &str) -> Result<Self, String>;
+}
+
+// Material, flange material, then the genes.
+fn beam_record(material: Material, flange: Material, genes: &[f64]) -> String {
+    let mut fields = vec![format!("{:?}", material), format!("{:?}", flange)];
+    fields.extend(genes.iter().map(|gene| gene.to_string()));
+    return fields.join(" ");
+}
+
+fn parse_beam_record(line: &str, count: usize) -> Result<(Material, Material, Vec<f64>), String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != count + 2 {
+        return Err(format!(
+            "Expected 2 materials and {} genes in {}",
+            count, line
+        ));
+    }
+    let material =
+        |name: &str| Material::from_str(name).map_err(|_| format!("Unknown material {}", name));
+    let genes = fields[2..]
+        .iter()
+        .map(|field| {
+            field
+                .parse::<f64>()
+                .map_err(|e| format!("Bad gene {}: {}", field, e))
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
+    return Ok((material(fields[0])?, material(fields[1])?, genes));
+}
+
+impl Record for RectBeam {
+    fn record(&self) -> String {
+        return beam_record(self.Material, self.FlangeMaterial, &self.genes());
+    }
+
+    fn from_record(line: &str) -> Result<RectBeam, String> {
+        let (material, flange, genes) = parse_beam_record(line, 4)?;
+        return Ok(RectBeam {
+            Material: material,
+            FlangeMaterial: flange,
+            Length: genes[0],
+            Height: genes[1],
+            Width: genes[2],
+            Thickness: genes[3],
+        });
+    }
+}
+
+impl Record for TBeam {
+    fn record(&self) -> String {
+        return beam_record(self.Material, self.FlangeMaterial, &self.genes());
+    }
+
+    fn from_record(line: &str) -> Result<TBeam, String> {
+        let (material, flange, genes) = parse_beam_record(line, 5)?;
+        return Ok(TBeam {
+            Material: material,
+            FlangeMaterial: flange,
+            Length: genes[0],
+            Height: genes[1],
+            Width: genes[2],
+            StemThickness: genes[3],
+            FlangeThickness: genes[4],
+        });
+    }
+}
+
+impl Record for IBeam {
+    fn record(&self) -> String {
+        return beam_record(self.Material, self.FlangeMaterial, &self.genes());
+    }
+
+    fn from_record(line: &str) -> Result<IBeam, String> {
+        let (material, flange, genes) = parse_beam_record(line, 5)?;
+        return Ok(IBeam {
+            Material: material,
+            FlangeMaterial: flange,
+            Length: genes[0],
+            Height: genes[1],
+            Width: genes[2],
+            CenterThickness: genes[3],
+            FlangeThickness: genes[4],
+        });
+    }
+}
+
+// The shape goes first, R, T or I.
+impl Record for Beams {
+    fn record(&self) -> String {
+        return match self {
+            Beams::R(a) => format!("R {}", a.record()),
+            Beams::T(a) => format!("T {}", a.record()),
+            Beams::I(a) => format!("I {}", a.record()),
+        };
+    }
+
+    fn from_record(line: &str) -> Result<Beams, String> {
+        let (shape, rest) = line.split_at(line.find(' ').unwrap_or(line.len()));
+        return match shape {
+            "R" => Ok(Beams::R(RectBeam::from_record(rest)?)),
+            "T" => Ok(Beams::T(TBeam::from_record(rest)?)),
+            "I" => Ok(Beams::I(IBeam::from_record(rest)?)),
+            _ => Err(format!("Unknown shape {}", shape)),
+        };
+    }
+}
+
+// Everything a simulation needs to carry on from the end of a generation. The generator is reseeded from the
+// simulation seed and generation at every checkpoint, so those two numbers stand in for its state.
+pub struct Checkpoint<T> {
+    pub generation: u64,
+    pub seed: u64,
+    pub stopper: String,
+    pub mutation: String,
+    pub best: T,
+    pub population: Vec<T>,
+    pub history: Vec<GenerationStats>,
+}
+
+impl<T: Phenotype<BeamFitness> + Copy> Checkpoint<T> {
+    // A simulation at the end of `generation`, with this thread's mutation step size.
+    pub fn capture(
+        generation: u64,
+        seed: u64,
+        stopper: &EarlyStopper,
+        population: &[T],
+        history: &[GenerationStats],
+    ) -> Checkpoint<T> {
+        return Checkpoint {
+            generation,
+            seed,
+            stopper: stopper.save(),
+            mutation: mutation::save_state(),
+            best: *population.iter().max_by_key(|x| x.fitness()).unwrap(),
+            population: population.to_vec(),
+            history: history.to_vec(),
+        };
+    }
+}
+
+pub fn path(dir: &str, name: &str) -> PathBuf {
+    return Path::new(dir).join(format!("{}.checkpoint", name));
+}
+
+// Writes to a temporary file first so a run killed mid-write keeps the previous checkpoint.
+pub fn save<T: Record>(path: &Path, checkpoint: &Checkpoint<T>) -> Result<(), String> {
+    let mut contents = format!(
+        "generation {}\nseed {}\nstopper {}\nmutation {}\nbest {}\n",
+        checkpoint.generation,
+        checkpoint.seed,
+        checkpoint.stopper,
+        checkpoint.mutation,
+        checkpoint.best.record()
+    );
+    for individual in &checkpoint.population {
+        contents.push_str(&format!("individual {}\n", individual.record()));
+    }
+    for stats in &checkpoint.history {
+        contents.push_str(&format!("history {}\n", stats.record()));
+    }
+    let temporary = path.with_extension("checkpoint.tmp");
+    return fs::write(&temporary, contents)
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|e| format!("Cannot write checkpoint {}: {}", path.display(), e));
+}
+
+// None when the simulation never reached its first checkpoint.
+pub fn load<T: Record>(path: &Path) -> Result<Option<Checkpoint<T>>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read checkpoint {}: {}", path.display(), e))?;
+    let error =
+        |line: usize, message: String| format!("{}:{}: {}", path.display(), line + 1, message);
+
+    let mut generation: Option<u64> = None;
+    let mut seed: Option<u64> = None;
+    let mut stopper: Option<String> = None;
+    let mut mutation: Option<String> = None;
+    let mut best: Option<T> = None;
+    let mut population: Vec<T> = Vec::new();
+    let mut history: Vec<GenerationStats> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let (key, value) = line.split_at(line.find(' ').unwrap_or(line.len()));
+        let value = value.trim();
+        match key {
+            "generation" => {
+                generation = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|e| error(index, e.to_string()))?,
+                )
+            }
+            "seed" => {
+                seed = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|e| error(index, e.to_string()))?,
+                )
+            }
+            "stopper" => stopper = Some(String::from(value)),
+            "mutation" => mutation = Some(String::from(value)),
+            "best" => best = Some(T::from_record(value).map_err(|e| error(index, e))?),
+            "individual" => population.push(T::from_record(value).map_err(|e| error(index, e))?),
+            "history" => {
+                history.push(GenerationStats::from_record(value).map_err(|e| error(index, e))?)
+            }
+            "" => continue,
+            _ => return Err(error(index, format!("unexpected {}", key))),
+        }
+    }
+
+    return match (generation, seed, stopper, mutation, best) {
+        (Some(generation), Some(seed), Some(stopper), Some(mutation), Some(best))
+            if !population.is_empty() =>
+        {
+            Ok(Some(Checkpoint {
+                generation,
+                seed,
+                stopper,
+                mutation,
+                best,
+                population,
+                history,
+            }))
+        }
+        _ => Err(format!("{}: incomplete checkpoint", path.display())),
+    };
+}
+
+// Loads a simulation's checkpoint and puts the stopper and this thread's mutation step size, constraint generation
+// and generator back as they were when it was saved. None when the simulation never reached its first checkpoint.
+pub fn resume<T: Record>(
+    dir: &str,
+    name: &str,
+    stopper: &mut EarlyStopper,
+) -> Result<Option<Checkpoint<T>>, String> {
+    let saved = match load::<T>(&path(dir, name))? {
+        Some(saved) => saved,
+        None => return Ok(None),
+    };
+    stopper.restore(&saved.stopper)?;
+    mutation::restore_state(&saved.mutation)?;
+    constraints::set_generation(saved.generation);
+    rng::seed(rng::derive_seed(saved.seed, saved.generation));
+    return Ok(Some(saved));
+}
+
+// The master seed of the run, so a resumed run seeds the simulations that had no checkpoint yet the same way.
+pub fn save_seed(dir: &str, seed: u64) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Cannot create checkpoint directory {}: {}", dir, e))?;
+    let path = Path::new(dir).join("seed");
+    return fs::write(&path, format!("{}\n", seed))
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e));
+}
+
+pub fn load_seed(dir: &str) -> Result<u64, String> {
+    let path = Path::new(dir).join("seed");
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    return contents
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("{}: {}", path.display(), e));
+}
+
+// A resumed run takes the seed it was checkpointed with. Otherwise without a seed pick one, so the run can still be
+// repeated from the printed output.
+pub fn resolve_seed(run_config: &mut Config) -> Result<(), String> {
+    match (&run_config.checkpoint_dir, run_config.resume) {
+        (Some(dir), true) => {
+            let saved = load_seed(dir)?;
+            if run_config.seed.is_some_and(|seed| seed != saved) {
+                return Err(format!(
+                    "Checkpoints in {} were saved with seed {}",
+                    dir, saved
+                ));
+            }
+            run_config.seed = Some(saved);
+        }
+        (Some(dir), false) => {
+            let seed = *run_config
+                .seed
+                .get_or_insert_with(|| rand::thread_rng().gen());
+            save_seed(dir, seed)?;
+        }
+        (None, _) => {
+            run_config
+                .seed
+                .get_or_insert_with(|| rand::thread_rng().gen());
+        }
+    }
+    return Ok(());
+}
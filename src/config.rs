@@ -0,0 +1,356 @@
+use crate::beams::{Material, ENG2_LOC, ROOM_TEMPERATURE};
+pub use crate::constraints::ConstraintHandling;
+pub use crate::crack::CrackType;
+pub use crate::crossover::CrossoverOperator;
+use crate::expression;
+pub use crate::expression::Spec;
+use crate::fatigue::{LoadSpectrum, MeanStressCorrection};
+pub use crate::fitness::Objective;
+pub use crate::laminate::PlyFailure;
+pub use crate::mutation::{BoundHandling, MutationOperator, MutationSettings};
+pub use crate::optimizer::Optimizer;
+use crate::plot::parse_axes;
+pub use crate::selection::SelectionStrategy;
+pub use crate::stopping::StoppingCriteria;
+pub use crate::sweep::Axis;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+// A named load case and the structure temperature (F) it is flown at.
+#[derive(Clone)]
+pub struct LoadCase {
+    pub name: String,
+    pub temperature: f64,
+}
+
+impl LoadCase {
+    // Accepts <name>:<temperature F>. Names become fos_<name> and deflection_<name> in --score expressions, so they
+    // have to be identifiers.
+    pub fn parse(text: &str) -> Result<LoadCase, String> {
+        let mut parts = text.splitn(2, ':');
+        return match (parts.next(), parts.next()) {
+            (Some(name), Some(_)) if !expression::is_identifier(name) => Err(format!(
+                "Load case name {} should be letters, digits and underscores, not starting with a digit",
+                name
+            )),
+            (Some(name), Some(temperature)) => Ok(LoadCase {
+                name: String::from(name),
+                temperature: parse_number("--load-case", temperature)?,
+            }),
+            _ => Err(format!(
+                "Load case {} should look like <name>:<temperature F>",
+                text
+            )),
+        };
+    }
+}
+
+// Run settings read from the command line. Set once in main before the simulations start.
+pub struct Config {
+    pub load_spectrum: Option<LoadSpectrum>,
+    pub mean_stress_correction: MeanStressCorrection,
+    pub damage_tolerance: Option<CrackType>,
+    pub detectable_crack: f64,
+    pub load_cases: Vec<LoadCase>,
+    pub ply_failure: PlyFailure,
+    pub flange_material: Option<Material>,
+    pub material_gene: bool,
+    pub shape_gene: bool,
+    pub length_bounds: Option<(f64, f64)>,
+    pub height_bounds: Option<(f64, f64)>,
+    pub seed: Option<u64>,
+    pub history_dir: Option<String>,
+    pub stopping: StoppingCriteria,
+    pub checkpoint_dir: Option<String>,
+    pub resume: bool,
+    pub selection: SelectionStrategy,
+    pub elitism: usize,
+    pub rect_crossover: CrossoverOperator,
+    pub t_crossover: CrossoverOperator,
+    pub i_crossover: CrossoverOperator,
+    pub mutation: MutationSettings,
+    pub constraint_handling: ConstraintHandling,
+    pub objective: Objective,
+    pub score: Option<Spec>,
+    pub refine: bool,
+    pub optimizer: Optimizer,
+    pub sweep: Option<String>,
+    pub grid: Vec<Axis>,
+    pub plot: Option<String>,
+    pub plot_axes: Option<(String, String)>,
+    pub drawing: Option<String>,
+    pub mesh: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        return Config {
+            load_spectrum: None,
+            mean_stress_correction: MeanStressCorrection::default(),
+            damage_tolerance: None,
+            detectable_crack: 0.05,
+            load_cases: vec![LoadCase {
+                name: String::from("room"),
+                temperature: ROOM_TEMPERATURE,
+            }],
+            ply_failure: PlyFailure::default(),
+            flange_material: None,
+            material_gene: false,
+            shape_gene: false,
+            length_bounds: None,
+            height_bounds: None,
+            seed: None,
+            history_dir: None,
+            stopping: StoppingCriteria::default(),
+            checkpoint_dir: None,
+            resume: false,
+            selection: SelectionStrategy::default(),
+            elitism: 0,
+            rect_crossover: CrossoverOperator::default(),
+            t_crossover: CrossoverOperator::default(),
+            i_crossover: CrossoverOperator::default(),
+            mutation: MutationSettings::default(),
+            constraint_handling: ConstraintHandling::default(),
+            objective: Objective::default(),
+            score: None,
+            refine: false,
+            optimizer: Optimizer::default(),
+            sweep: None,
+            grid: Vec::new(),
+            plot: None,
+            plot_axes: None,
+            drawing: None,
+            mesh: None,
+        };
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        panic!("Run configuration was already set.");
+    }
+}
+
+pub fn get() -> &'static Config {
+    return CONFIG.get_or_init(Config::default);
+}
+
+const USAGE: &str = "Options:
+  --load-history <file>   CSV of time (s) vs n_z, rainflow counted for fatigue life
+  --mean-stress <model>   goodman (default), gerber, soderberg, walker or walker:<gamma>
+  --damage-tolerance <type> Paris law crack growth for the winning beam, edge or through crack
+  --detectable-crack <in>   Crack length found by inspection (default 0.05)
+  --load-case <name>:<F>    Load case flown at a temperature, repeat for several (default room:70)
+  --ply-failure <criterion> First ply failure for laminates, tsai-wu (default) or max-stress
+  --flange-material <name>  Flange material for hybrid sections, webs use each material in turn
+  --material-gene           Optimize material as part of the genome, one population per shape
+  --shape-gene              Optimize the section type as part of the genome, one population for all shapes
+  --length <min>:<max>      Make the beam length a design variable within these bounds (in, min at least 879)
+  --height <min>:<max>      Make the beam height a design variable within these bounds (in)
+  --seed <n>                Master seed for a repeatable run (default random, printed with the result)
+  --history <dir>           Write a convergence history CSV per material and shape to this directory
+  --stagnation <n>          Stop a simulation after n generations without improving its best score
+  --fitness-delta <d>:<n>   Stop a simulation when its best score improves by less than d over n generations
+  --feasible-stable <n>     Stop a simulation once its best design is feasible and unchanged for n generations
+  --time-budget <s>         Stop every simulation after this many seconds of wall-clock time
+  --checkpoint <dir>        Save every simulation to this directory every 100 generations
+  --resume                  Carry on from the checkpoints in the --checkpoint directory, run with the same options
+  --selection <strategy>    maximize (default), tournament, tournament:<size>, roulette, sus or rank
+  --elitism <n>             Best n individuals of each generation are never killed off (default 0)
+  --crossover <operator>    mean (default), sbx, sbx:<eta>, blx, blx:<alpha> or uniform, for every beam type
+  --rect-crossover <operator> Crossover for rectangular beams only, likewise --t-crossover and --i-crossover
+  --mutation <operator>     uniform (default), gaussian, gaussian:<sigma>, polynomial or polynomial:<eta>
+  --mutation-bounds <mode>  ignore, clamp (default) or reflect genes mutated past their bounds
+  --mutation-rate <p>       Chance each gene mutates (default 1)
+  --adaptive-mutation       Adapt the mutation step size by the 1/5th success rule
+  --constraints <method>    penalty, penalty:<weight>, deb (default), epsilon or epsilon:<level>:<generations>
+  --objective <objective>   weight, cost, margin or weighted:<weight>:<cost>:<margin> (default weighted:1:0.333:78000)
+  --score <spec>            Score with an expression in place of the objective and requirements, e.g.
+                            \"minimize weight + 0.5*cost subject to fos in [1.4, 2.2], abs(deflection) <= 70\"
+                            over length, height, width, weight, cost, fos, deflection, flight_hours, tension,
+                            compression, ply_margin_tension and ply_margin_compression, fos_<case> and
+                            deflection_<case> per load case, and with --damage-tolerance critical_crack,
+                            hours_to_critical and inspection_interval
+  --refine                  Polish each simulation's best beam with an augmented Lagrangian local search
+  --optimizer <method>      ga (default), pso, de, de:<f>:<cr>, sa or sa:<temperature>; selection, crossover,
+                            mutation and elitism only apply to ga
+  --sweep <dir>             Evaluate every beam type and material over a grid instead of optimizing, writing a CSV
+                            per type to this directory, at most 2000000 designs per type
+  --grid <gene>=<min>:<max>:<n> Grid of one gene for --sweep (default 31 points over its bounds), repeat for several
+  --plot <dir>              Write SVG contour maps of weight, fos, deflection and flight hours around the best beam
+  --plot-axes <gene>:<gene> Genes on the plot axes (default the first two the bounds leave free)
+  --drawing <dir>           Write the best beam's cross-section to this directory as a dimensioned SVG and a DXF
+  --mesh <dir>              Write the best beam as a solid extruded over its length to this directory as STL and OBJ
+                            (inches)";
+
+pub fn from_args(args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut args = args;
+    let mut load_cases: Vec<LoadCase> = Vec::new();
+    let mut score: Option<String> = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--load-history" => config.load_spectrum = Some(LoadSpectrum::load(&value()?)?),
+            "--mean-stress" => {
+                config.mean_stress_correction = MeanStressCorrection::parse(&value()?)?
+            }
+            "--damage-tolerance" => config.damage_tolerance = Some(CrackType::parse(&value()?)?),
+            "--detectable-crack" => config.detectable_crack = parse_positive(&arg, &value()?)?,
+            "--load-case" => load_cases.push(LoadCase::parse(&value()?)?),
+            "--ply-failure" => config.ply_failure = PlyFailure::parse(&value()?)?,
+            "--flange-material" => config.flange_material = Some(parse_material(&value()?)?),
+            "--material-gene" => config.material_gene = true,
+            "--shape-gene" => config.shape_gene = true,
+            "--length" => {
+                let bounds = parse_bounds(&arg, &value()?)?;
+                if bounds.0 < ENG2_LOC {
+                    return Err(format!(
+                        "--length must not go below the outboard engine station {}",
+                        ENG2_LOC
+                    ));
+                }
+                config.length_bounds = Some(bounds);
+            }
+            "--height" => config.height_bounds = Some(parse_bounds(&arg, &value()?)?),
+            "--seed" => config.seed = Some(parse_count(&arg, &value()?)?),
+            "--history" => config.history_dir = Some(value()?),
+            "--stagnation" => config.stopping.stagnation = Some(parse_count(&arg, &value()?)?),
+            "--fitness-delta" => {
+                config.stopping.fitness_delta = Some(parse_delta(&arg, &value()?)?)
+            }
+            "--feasible-stable" => {
+                config.stopping.feasible_stable = Some(parse_count(&arg, &value()?)?)
+            }
+            "--time-budget" => {
+                let seconds = parse_number(&arg, &value()?)?;
+                config.stopping.time_budget =
+                    Some(Duration::try_from_secs_f64(seconds).map_err(|_| {
+                        format!("{} must be a finite number of seconds, 0 or more", arg)
+                    })?)
+            }
+            "--checkpoint" => config.checkpoint_dir = Some(value()?),
+            "--resume" => config.resume = true,
+            "--selection" => config.selection = SelectionStrategy::parse(&value()?)?,
+            "--elitism" => config.elitism = parse_count(&arg, &value()?)? as usize,
+            "--crossover" => {
+                let operator = CrossoverOperator::parse(&value()?)?;
+                config.rect_crossover = operator;
+                config.t_crossover = operator;
+                config.i_crossover = operator;
+            }
+            "--rect-crossover" => config.rect_crossover = CrossoverOperator::parse(&value()?)?,
+            "--t-crossover" => config.t_crossover = CrossoverOperator::parse(&value()?)?,
+            "--i-crossover" => config.i_crossover = CrossoverOperator::parse(&value()?)?,
+            "--mutation" => config.mutation.operator = MutationOperator::parse(&value()?)?,
+            "--mutation-bounds" => config.mutation.bounds = BoundHandling::parse(&value()?)?,
+            "--mutation-rate" => config.mutation.rate = parse_fraction(&arg, &value()?)?,
+            "--adaptive-mutation" => config.mutation.one_fifth = true,
+            "--constraints" => config.constraint_handling = ConstraintHandling::parse(&value()?)?,
+            "--objective" => config.objective = Objective::parse(&value()?)?,
+            "--score" => score = Some(value()?),
+            "--refine" => config.refine = true,
+            "--optimizer" => config.optimizer = Optimizer::parse(&value()?)?,
+            "--sweep" => config.sweep = Some(value()?),
+            "--grid" => config.grid.push(Axis::parse(&value()?)?),
+            "--plot" => config.plot = Some(value()?),
+            "--plot-axes" => config.plot_axes = Some(parse_axes(&value()?)?),
+            "--drawing" => config.drawing = Some(value()?),
+            "--mesh" => config.mesh = Some(value()?),
+            "--help" | "-h" => return Err(String::from(USAGE)),
+            _ => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+        }
+    }
+
+    if !load_cases.is_empty() {
+        config.load_cases = load_cases;
+    }
+    // Parsed last, the per load case and damage tolerance variables depend on the other options.
+    if let Some(text) = score {
+        let cases: Vec<String> = config
+            .load_cases
+            .iter()
+            .map(|case| case.name.clone())
+            .collect();
+        let variables = expression::variables(&cases, config.damage_tolerance.is_some());
+        config.score = Some(Spec::parse(&text, &variables)?);
+    }
+    if config.resume && config.checkpoint_dir.is_none() {
+        return Err(String::from(
+            "--resume needs the --checkpoint directory to resume from",
+        ));
+    }
+    return Ok(config);
+}
+
+fn parse_number(arg: &str, value: &str) -> Result<f64, String> {
+    return value
+        .parse::<f64>()
+        .map_err(|e| format!("Bad value {} for {}: {}", value, arg, e));
+}
+
+fn parse_positive(arg: &str, value: &str) -> Result<f64, String> {
+    let number = parse_number(arg, value)?;
+    return match number > 0.0 {
+        true => Ok(number),
+        false => Err(format!("{} must be greater than 0", arg)),
+    };
+}
+
+fn parse_fraction(arg: &str, value: &str) -> Result<f64, String> {
+    let number = parse_number(arg, value)?;
+    return match (0.0..=1.0).contains(&number) {
+        true => Ok(number),
+        false => Err(format!("{} must be between 0 and 1", arg)),
+    };
+}
+
+fn parse_count(arg: &str, value: &str) -> Result<u64, String> {
+    return value
+        .parse::<u64>()
+        .map_err(|e| format!("Bad value {} for {}: {}", value, arg, e));
+}
+
+fn parse_bounds(arg: &str, value: &str) -> Result<(f64, f64), String> {
+    let mut parts = value.splitn(2, ':');
+    return match (parts.next(), parts.next()) {
+        (Some(min), Some(max)) => {
+            let bounds = (parse_number(arg, min)?, parse_number(arg, max)?);
+            match (bounds.0 <= bounds.1, bounds.0 > 0.0) {
+                (true, true) => Ok(bounds),
+                (false, _) => Err(format!("Bounds for {} must be <min>:<max>", arg)),
+                (true, false) => Err(format!("Bounds for {} must be greater than 0", arg)),
+            }
+        }
+        _ => Err(format!("Bounds for {} must be <min>:<max>", arg)),
+    };
+}
+
+fn parse_delta(arg: &str, value: &str) -> Result<(f64, u64), String> {
+    let mut parts = value.splitn(2, ':');
+    return match (parts.next(), parts.next()) {
+        (Some(delta), Some(generations)) => {
+            Ok((parse_number(arg, delta)?, parse_count(arg, generations)?))
+        }
+        _ => Err(format!("{} must be <delta>:<generations>", arg)),
+    };
+}
+
+fn parse_material(name: &str) -> Result<Material, String> {
+    return Material::from_str(name).map_err(|_| {
+        let names: Vec<String> = Material::iter().map(|m| format!("{:?}", m)).collect();
+        format!(
+            "Unknown material {}, expected one of {}",
+            name,
+            names.join(", ")
+        )
+    });
+}
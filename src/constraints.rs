@@ -0,0 +1,168 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+// Any one requirement can't count for more than this, which also stands in for NaN and infinite results.
+const MAX_VIOLATION: f64 = 1.0e3;
+// Under Deb's rules every infeasible design scores below this, and every feasible design above it.
+const INFEASIBLE_SCORE: f64 = -1.0e15;
+// Default weight of --constraints penalty.
+const PENALTY_WEIGHT: f64 = 1.0e6;
+// Converts a violation to score units below INFEASIBLE_SCORE.
+const VIOLATION_SCALE: f64 = 1.0e6;
+// Evaluations each simulation thread remembers before starting over, a few generations' worth.
+const CACHE_SIZE: usize = 20_000;
+
+// What a design scores before its requirements are considered, and how far it is outside them. Each requirement
+// adds its shortfall as a fraction of its limit, so 0.1 is a limit missed by 10%.
+#[derive(Copy, Clone)]
+pub struct Evaluation {
+    pub objective: f64,
+    pub violation: f64,
+}
+
+impl Evaluation {
+    pub fn is_feasible(&self) -> bool {
+        return self.violation == 0.0;
+    }
+}
+
+pub trait Evaluate {
+    fn evaluation(&self) -> Evaluation;
+}
+
+fn limit_violation(shortfall: f64, limit: f64) -> f64 {
+    let scale = match limit == 0.0 {
+        true => 1.0,
+        false => limit.abs(),
+    };
+    return match shortfall.is_nan() {
+        true => MAX_VIOLATION,
+        false => (shortfall / scale).clamp(0.0, MAX_VIOLATION),
+    };
+}
+
+// Violation of value >= min.
+pub fn below(value: f64, min: f64) -> f64 {
+    return limit_violation(min - value, min);
+}
+
+// Violation of value <= max.
+pub fn above(value: f64, max: f64) -> f64 {
+    return limit_violation(value - max, max);
+}
+
+pub fn outside(value: f64, min: f64, max: f64) -> f64 {
+    return below(value, min) + above(value, max);
+}
+
+#[derive(Copy, Clone)]
+pub enum ConstraintHandling {
+    // Objective less the weight times the violation.
+    Penalty(f64),
+    // Feasible beats infeasible, feasible designs compare by objective and infeasible ones by violation.
+    Deb,
+    // Deb's rules, counting a violation up to epsilon as feasible. Epsilon starts at the given level and shrinks to 0
+    // over the given number of generations.
+    Epsilon(f64, u64),
+}
+
+// A penalty small enough to trade against the objective lets a slightly infeasible design win, Deb's rules never do.
+impl Default for ConstraintHandling {
+    fn default() -> ConstraintHandling {
+        return ConstraintHandling::Deb;
+    }
+}
+
+thread_local! {
+    // Generation the simulation on this thread is breeding. Outside a simulation epsilon is always 0.
+    static GENERATION: Cell<u64> = const { Cell::new(u64::MAX) };
+    // Evaluations by design. Selection, the kill off, the statistics and the 1/5th rule all score the same
+    // individuals each generation.
+    static EVALUATIONS: RefCell<HashMap<String, Evaluation>> = RefCell::new(HashMap::new());
+}
+
+// Evaluation of the design identified by `key`, from `evaluate` the first time it is asked for. Evaluations don't
+// depend on the generation, only the fitness made from them does.
+pub fn cached(key: String, evaluate: impl FnOnce() -> Evaluation) -> Evaluation {
+    if let Some(evaluation) = EVALUATIONS.with(|cache| cache.borrow().get(&key).copied()) {
+        return evaluation;
+    }
+    let evaluation = evaluate();
+    EVALUATIONS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(key, evaluation);
+    });
+    return evaluation;
+}
+
+pub fn set_generation(generation: u64) {
+    GENERATION.with(|g| g.set(generation));
+}
+
+impl ConstraintHandling {
+    // Accepts penalty, penalty:<weight>, deb, epsilon or epsilon:<level>:<generations>.
+    pub fn parse(name: &str) -> Result<ConstraintHandling, String> {
+        let lower = name.to_lowercase();
+        let parts: Vec<&str> = lower.split(':').collect();
+        let number = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| format!("Bad constraint handling parameter {}: {}", value, e))
+        };
+        return match parts.as_slice() {
+            ["penalty"] => Ok(ConstraintHandling::Penalty(PENALTY_WEIGHT)),
+            ["penalty", weight] => Ok(ConstraintHandling::Penalty(number(weight)?)),
+            ["deb"] => Ok(ConstraintHandling::Deb),
+            ["epsilon"] => Ok(ConstraintHandling::Epsilon(1.0, 1000)),
+            ["epsilon", level, generations] => Ok(ConstraintHandling::Epsilon(
+                number(level)?,
+                generations
+                    .parse::<u64>()
+                    .map_err(|e| format!("Bad epsilon generations {}: {}", generations, e))?,
+            )),
+            _ => Err(format!("Unknown constraint handling {}", name)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            ConstraintHandling::Penalty(weight) => format!("penalty (weight {})", weight),
+            ConstraintHandling::Deb => String::from("Deb's feasibility rules"),
+            ConstraintHandling::Epsilon(level, generations) => format!(
+                "epsilon constraint ({} shrinking to 0 over {} generations)",
+                level, generations
+            ),
+        };
+    }
+
+    fn epsilon(&self) -> f64 {
+        return match *self {
+            ConstraintHandling::Epsilon(level, generations) => {
+                let generation = GENERATION.with(|g| g.get());
+                match generation < generations {
+                    true => level * f64::powf(1.0 - generation as f64 / generations as f64, 2.0),
+                    false => 0.0,
+                }
+            }
+            _ => 0.0,
+        };
+    }
+
+    pub fn fitness(&self, evaluation: Evaluation) -> f64 {
+        return match *self {
+            ConstraintHandling::Penalty(weight) => {
+                evaluation.objective - weight * evaluation.violation
+            }
+            ConstraintHandling::Deb | ConstraintHandling::Epsilon(_, _) => {
+                match evaluation.violation <= self.epsilon() {
+                    // Keep feasible scores clear of the infeasible range.
+                    true => evaluation.objective.max(INFEASIBLE_SCORE / 10.0),
+                    false => INFEASIBLE_SCORE - VIOLATION_SCALE * evaluation.violation,
+                }
+            }
+        };
+    }
+}
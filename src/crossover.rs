@@ -0,0 +1,85 @@
+use crate::rng;
+use rand::Rng;
+
+// Real-coded crossover on the genes of two parents, giving one child.
+#[derive(Copy, Clone)]
+pub enum CrossoverOperator {
+    // Arithmetic mean of the parents, what the simulations always did.
+    Mean,
+    // Simulated binary crossover with distribution index eta, larger keeps children closer to the parents.
+    Sbx(f64),
+    // Uniform in the parents' range stretched by alpha times its width on both sides.
+    Blx(f64),
+    // Each gene from either parent with equal chance.
+    Uniform,
+}
+
+impl Default for CrossoverOperator {
+    fn default() -> CrossoverOperator {
+        return CrossoverOperator::Mean;
+    }
+}
+
+impl CrossoverOperator {
+    // Accepts mean, sbx, sbx:<eta>, blx, blx:<alpha> or uniform.
+    pub fn parse(name: &str) -> Result<CrossoverOperator, String> {
+        let lower = name.to_lowercase();
+        let mut parts = lower.splitn(2, ':');
+        let parameter = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| format!("Bad crossover parameter {}: {}", value, e))
+        };
+        return match (parts.next(), parts.next()) {
+            (Some("mean"), None) => Ok(CrossoverOperator::Mean),
+            (Some("sbx"), None) => Ok(CrossoverOperator::Sbx(15.0)),
+            (Some("sbx"), Some(eta)) => Ok(CrossoverOperator::Sbx(parameter(eta)?)),
+            (Some("blx"), None) => Ok(CrossoverOperator::Blx(0.5)),
+            (Some("blx"), Some(alpha)) => Ok(CrossoverOperator::Blx(parameter(alpha)?)),
+            (Some("uniform"), None) => Ok(CrossoverOperator::Uniform),
+            _ => Err(format!("Unknown crossover operator {}", name)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            CrossoverOperator::Mean => String::from("mean"),
+            CrossoverOperator::Sbx(eta) => format!("SBX (eta {})", eta),
+            CrossoverOperator::Blx(alpha) => format!("BLX (alpha {})", alpha),
+            CrossoverOperator::Uniform => String::from("uniform"),
+        };
+    }
+
+    // Genes the parents agree on, such as a fixed length or height, pass on unchanged with every operator.
+    pub fn cross(&self, first: &[f64], second: &[f64]) -> Vec<f64> {
+        let mut rng = rng::thread_rng();
+        return first
+            .iter()
+            .zip(second)
+            .map(|(&a, &b)| match *self {
+                CrossoverOperator::Mean => (a + b) / 2.0,
+                CrossoverOperator::Sbx(eta) => {
+                    let u: f64 = rng.gen();
+                    let beta = match u <= 0.5 {
+                        true => f64::powf(2.0 * u, 1.0 / (eta + 1.0)),
+                        false => f64::powf(1.0 / (2.0 * (1.0 - u)), 1.0 / (eta + 1.0)),
+                    };
+                    // Either of the two SBX children.
+                    match rng.gen::<bool>() {
+                        true => 0.5 * ((1.0 + beta) * a + (1.0 - beta) * b),
+                        false => 0.5 * ((1.0 - beta) * a + (1.0 + beta) * b),
+                    }
+                }
+                CrossoverOperator::Blx(alpha) => {
+                    let (low, high) = (a.min(b), a.max(b));
+                    let spread = alpha * (high - low);
+                    low - spread + rng.gen::<f64>() * (high - low + 2.0 * spread)
+                }
+                CrossoverOperator::Uniform => match rng.gen::<bool>() {
+                    true => a,
+                    false => b,
+                },
+            })
+            .collect();
+    }
+}
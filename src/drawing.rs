@@ -0,0 +1,479 @@
+use crate::beams::{Beams, Plate, Plates, Section, TransformedSection, ROOM_TEMPERATURE};
+use std::fs;
+use std::path::Path;
+
+const WIDTH: f64 = 780.0;
+const HEIGHT: f64 = 620.0;
+// Area the section is scaled into, leaving room for the title, dimensions and thickness callouts.
+const LEFT: f64 = 110.0;
+const RIGHT: f64 = 520.0;
+const TOP: f64 = 90.0;
+const BOTTOM: f64 = 540.0;
+const CALLOUTS: f64 = 570.0;
+// Dimension lines sit this far off the section, in drawing units of the SVG.
+const OFFSET: f64 = 35.0;
+// Fills for the web and flange materials.
+const FILLS: [&str; 2] = ["#9ecae1", "#fdae6b"];
+
+// The union of a section's plates on the grid of their edges. Cells and corners are indices into `xs` and `ys`,
+// in section coordinates (y down from the top left corner).
+pub struct Region {
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    // Filled cells by their top left corner.
+    pub cells: Vec<(i64, i64)>,
+    // Edges between filled and empty cells, directed with the filled cell on their right.
+    pub boundary: Vec<((i64, i64), (i64, i64))>,
+}
+
+pub fn region(plates: &[Plate]) -> Region {
+    let plates: Vec<&Plate> = plates
+        .iter()
+        .filter(|p| p.width > 0.0 && p.height > 0.0)
+        .collect();
+    let edges = |lower: &dyn Fn(&Plate) -> f64, size: &dyn Fn(&Plate) -> f64| {
+        let mut values: Vec<f64> = plates
+            .iter()
+            .flat_map(|p| [lower(p), lower(p) + size(p)])
+            .collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup_by(|a, b| (*a - *b).abs() < 1.0e-9);
+        return values;
+    };
+    let xs = edges(&|p| p.x - p.width / 2.0, &|p| p.width);
+    let ys = edges(&|p| p.y - p.height / 2.0, &|p| p.height);
+    if xs.len() < 2 || ys.len() < 2 {
+        return Region {
+            xs,
+            ys,
+            cells: Vec::new(),
+            boundary: Vec::new(),
+        };
+    }
+
+    // Cells between neighbouring edges, filled when some plate covers their centre.
+    let covered = |i: i64, j: i64| {
+        if i < 0 || j < 0 || i as usize >= xs.len() - 1 || j as usize >= ys.len() - 1 {
+            return false;
+        }
+        let x = (xs[i as usize] + xs[i as usize + 1]) / 2.0;
+        let y = (ys[j as usize] + ys[j as usize + 1]) / 2.0;
+        return plates
+            .iter()
+            .any(|p| (x - p.x).abs() < p.width / 2.0 && (y - p.y).abs() < p.height / 2.0);
+    };
+
+    let mut cells: Vec<(i64, i64)> = Vec::new();
+    let mut boundary: Vec<((i64, i64), (i64, i64))> = Vec::new();
+    for i in 0..xs.len() as i64 - 1 {
+        for j in 0..ys.len() as i64 - 1 {
+            if !covered(i, j) {
+                continue;
+            }
+            cells.push((i, j));
+            if !covered(i, j - 1) {
+                boundary.push(((i, j), (i + 1, j)));
+            }
+            if !covered(i + 1, j) {
+                boundary.push(((i + 1, j), (i + 1, j + 1)));
+            }
+            if !covered(i, j + 1) {
+                boundary.push(((i + 1, j + 1), (i, j + 1)));
+            }
+            if !covered(i - 1, j) {
+                boundary.push(((i, j + 1), (i, j)));
+            }
+        }
+    }
+    return Region {
+        xs,
+        ys,
+        cells,
+        boundary,
+    };
+}
+
+// Closed loops around the union of the plates, in section coordinates. Outer boundaries and holes alike, each with
+// only its corners.
+pub fn outline(plates: &[Plate]) -> Vec<Vec<(f64, f64)>> {
+    let Region {
+        xs,
+        ys,
+        mut boundary,
+        ..
+    } = region(plates);
+    let mut loops = Vec::new();
+    while let Some((start, mut next)) = boundary.pop() {
+        let mut corners = vec![start];
+        while next != start {
+            corners.push(next);
+            let index = match boundary.iter().position(|&(from, _)| from == next) {
+                Some(index) => index,
+                None => break,
+            };
+            next = boundary.swap_remove(index).1;
+        }
+        // Only the corners where the direction turns.
+        let count = corners.len();
+        let turning: Vec<(f64, f64)> = (0..count)
+            .filter(|&k| {
+                let (a, b, c) = (
+                    corners[(k + count - 1) % count],
+                    corners[k],
+                    corners[(k + 1) % count],
+                );
+                (b.0 - a.0) * (c.1 - b.1) != (b.1 - a.1) * (c.0 - b.0)
+            })
+            .map(|k| (xs[corners[k].0 as usize], ys[corners[k].1 as usize]))
+            .collect();
+        loops.push(turning);
+    }
+    return loops;
+}
+
+// Bounding box of the plates with some area, min x, min y, max x, max y. A section without one has nothing to draw.
+fn extent(plates: &[Plate]) -> Result<(f64, f64, f64, f64), String> {
+    let mut bounds = (
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+    );
+    for p in plates.iter().filter(|p| p.width > 0.0 && p.height > 0.0) {
+        bounds.0 = bounds.0.min(p.x - p.width / 2.0);
+        bounds.1 = bounds.1.min(p.y - p.height / 2.0);
+        bounds.2 = bounds.2.max(p.x + p.width / 2.0);
+        bounds.3 = bounds.3.max(p.y + p.height / 2.0);
+    }
+    return match [bounds.0, bounds.1, bounds.2, bounds.3]
+        .iter()
+        .all(|b| b.is_finite())
+    {
+        true => Ok(bounds),
+        false => Err(String::from(
+            "Cannot draw a section without a plate of finite, positive width and height",
+        )),
+    };
+}
+
+// Plates called out by thickness, each distinct flange or web thickness once.
+fn callouts(plates: &[Plate]) -> Vec<(&Plate, f64)> {
+    let mut called: Vec<(&Plate, f64)> = Vec::new();
+    for plate in plates.iter().filter(|p| p.width > 0.0 && p.height > 0.0) {
+        let thickness = plate.width.min(plate.height);
+        if !called
+            .iter()
+            .any(|(p, t)| p.flange == plate.flange && (t - thickness).abs() < 1.0e-9)
+        {
+            called.push((plate, thickness));
+        }
+    }
+    return called;
+}
+
+fn arrow(x1: f64, y1: f64, x2: f64, y2: f64) -> String {
+    return format!(
+        "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\" marker-start=\"url(#arrow)\" marker-end=\"url(#arrow)\"/>\n",
+        x1, y1, x2, y2
+    );
+}
+
+// Cross-section scaled to fit, plates filled by material, overall and plate dimensions in inches, and the
+// centroid with both neutral axes.
+pub fn write_svg(
+    path: &Path,
+    title: &str,
+    plates: &[Plate],
+    section: &Section,
+) -> Result<(), String> {
+    let (min_x, min_y, max_x, max_y) = extent(plates)?;
+    let scale = f64::min(
+        (RIGHT - LEFT) / (max_x - min_x),
+        (BOTTOM - TOP) / (max_y - min_y),
+    );
+    let left = LEFT + ((RIGHT - LEFT) - (max_x - min_x) * scale) / 2.0;
+    let top = TOP + ((BOTTOM - TOP) - (max_y - min_y) * scale) / 2.0;
+    let x_of = |x: f64| left + (x - min_x) * scale;
+    let y_of = |y: f64| top + (y - min_y) * scale;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        WIDTH, HEIGHT
+    );
+    svg.push_str("<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\"><path d=\"M0 0L10 5L0 10z\"/></marker></defs>\n");
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n<text x=\"{}\" y=\"30\" font-size=\"16\" text-anchor=\"middle\">{}</text>\n",
+        WIDTH,
+        HEIGHT,
+        WIDTH / 2.0,
+        title
+    ));
+
+    for plate in plates.iter().filter(|p| p.width > 0.0 && p.height > 0.0) {
+        svg.push_str(&format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>\n",
+            x_of(plate.x - plate.width / 2.0),
+            y_of(plate.y - plate.height / 2.0),
+            plate.width * scale,
+            plate.height * scale,
+            FILLS[plate.flange as usize]
+        ));
+    }
+    for corners in outline(plates) {
+        let points: Vec<String> = corners
+            .iter()
+            .map(|&(x, y)| format!("{:.2},{:.2}", x_of(x), y_of(y)))
+            .collect();
+        svg.push_str(&format!(
+            "<polygon points=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"1.5\"/>\n",
+            points.join(" ")
+        ));
+    }
+
+    // Overall width above the section and height to its left, with extension lines.
+    let (x0, x1, y0, y1) = (x_of(min_x), x_of(max_x), y_of(min_y), y_of(max_y));
+    svg.push_str(&format!(
+        "<path d=\"M{0:.2} {1:.2}V{2:.2}M{3:.2} {1:.2}V{2:.2}M{4:.2} {5:.2}H{6:.2}M{4:.2} {7:.2}H{6:.2}\" stroke=\"black\" stroke-width=\"0.5\"/>\n",
+        x0,
+        y0 - 5.0,
+        y0 - OFFSET - 5.0,
+        x1,
+        x0 - 5.0,
+        y0,
+        x0 - OFFSET - 5.0,
+        y1
+    ));
+    svg.push_str(&arrow(x0, y0 - OFFSET, x1, y0 - OFFSET));
+    svg.push_str(&format!(
+        "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{:.4}</text>\n",
+        (x0 + x1) / 2.0,
+        y0 - OFFSET - 6.0,
+        max_x - min_x
+    ));
+    svg.push_str(&arrow(x0 - OFFSET, y0, x0 - OFFSET, y1));
+    svg.push_str(&format!(
+        "<text x=\"{0:.2}\" y=\"{1:.2}\" text-anchor=\"middle\" transform=\"rotate(-90 {0:.2} {1:.2})\">{2:.4}</text>\n",
+        x0 - OFFSET - 6.0,
+        (y0 + y1) / 2.0,
+        max_y - min_y
+    ));
+
+    // Thickness callouts on the right, with leaders to their plates.
+    for (k, (plate, thickness)) in callouts(plates).iter().enumerate() {
+        let y = TOP + 30.0 * k as f64;
+        svg.push_str(&format!(
+            "<path d=\"M{:.2} {:.2}L{:.2} {:.2}H{:.2}\" fill=\"none\" stroke=\"black\" stroke-width=\"0.5\"/><circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"2\"/><text x=\"{:.2}\" y=\"{:.2}\">t {:.4} ({} {:?})</text>\n",
+            x_of(plate.x),
+            y_of(plate.y),
+            CALLOUTS - 10.0,
+            y,
+            CALLOUTS,
+            x_of(plate.x),
+            y_of(plate.y),
+            CALLOUTS + 4.0,
+            y + 4.0,
+            thickness,
+            match plate.flange {
+                true => "flange",
+                false => "web",
+            },
+            plate.material
+        ));
+    }
+
+    // Neutral axes through the centroid, dash-dotted past the section.
+    let (cx, cy) = (x_of(section.x_bend), y_of(section.y_bend));
+    svg.push_str(&format!(
+        "<path d=\"M{:.2} {:.2}H{:.2}M{:.2} {:.2}V{:.2}\" stroke=\"red\" stroke-dasharray=\"12 3 2 3\"/>\n",
+        x0 - 15.0,
+        cy,
+        x1 + 15.0,
+        cx,
+        y0 - 15.0,
+        y1 + 15.0
+    ));
+    svg.push_str(&format!(
+        "<circle cx=\"{0:.2}\" cy=\"{1:.2}\" r=\"5\" fill=\"none\" stroke=\"red\" stroke-width=\"1.5\"/><text x=\"{2:.2}\" y=\"{3:.2}\" fill=\"red\">x-x</text><text x=\"{0:.2}\" y=\"{4:.2}\" fill=\"red\" text-anchor=\"middle\">y-y</text>\n",
+        cx,
+        cy,
+        x1 + 18.0,
+        cy + 4.0,
+        y1 + 30.0
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\">Centroid ({:.4}, {:.4}) from top left, Ix {:.4e}, Iy {:.4e}</text>\n</svg>\n",
+        LEFT,
+        HEIGHT - 25.0,
+        section.x_bend,
+        section.y_bend,
+        section.ix,
+        section.iy
+    ));
+    return fs::write(path, svg)
+        .map_err(|e| format!("Cannot write drawing {}: {}", path.display(), e));
+}
+
+// One DXF group: code and value lines.
+fn group(dxf: &mut String, code: u32, value: impl std::fmt::Display) {
+    dxf.push_str(&format!("{}\n{}\n", code, value));
+}
+
+fn dxf_line(dxf: &mut String, layer: &str, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+    group(dxf, 0, "LINE");
+    group(dxf, 8, layer);
+    for (code, value) in [(10, x1), (20, y1), (11, x2), (21, y2)] {
+        group(dxf, code, value);
+    }
+}
+
+fn dxf_text(dxf: &mut String, layer: &str, (x, y): (f64, f64), height: f64, text: &str) {
+    group(dxf, 0, "TEXT");
+    group(dxf, 8, layer);
+    group(dxf, 10, x);
+    group(dxf, 20, y);
+    group(dxf, 40, height);
+    group(dxf, 1, text);
+}
+
+// AutoCAD R12 ASCII DXF in inches with y up and the origin at the bottom left of the section. The outline is closed
+// polylines on layer SECTION, the neutral axes and centroid are on CENTERLINES and the overall dimensions on
+// DIMENSIONS. R12 has no units header variable ($INSUNITS came with R2000), so inches are by convention only.
+pub fn write_dxf(path: &Path, plates: &[Plate], section: &Section) -> Result<(), String> {
+    let (min_x, min_y, max_x, max_y) = extent(plates)?;
+    let point = |x: f64, y: f64| (x - min_x, max_y - y);
+    let (width, height) = (max_x - min_x, max_y - min_y);
+    let offset = 0.1 * width.max(height);
+    let text_height = 0.03 * width.max(height);
+
+    let mut dxf = String::new();
+    group(&mut dxf, 0, "SECTION");
+    group(&mut dxf, 2, "HEADER");
+    group(&mut dxf, 9, "$ACADVER");
+    group(&mut dxf, 1, "AC1009");
+    group(&mut dxf, 0, "ENDSEC");
+    group(&mut dxf, 0, "SECTION");
+    group(&mut dxf, 2, "ENTITIES");
+
+    for corners in outline(plates) {
+        group(&mut dxf, 0, "POLYLINE");
+        group(&mut dxf, 8, "SECTION");
+        group(&mut dxf, 66, 1);
+        group(&mut dxf, 70, 1);
+        for &(x, y) in &corners {
+            let (x, y) = point(x, y);
+            group(&mut dxf, 0, "VERTEX");
+            group(&mut dxf, 8, "SECTION");
+            group(&mut dxf, 10, x);
+            group(&mut dxf, 20, y);
+        }
+        group(&mut dxf, 0, "SEQEND");
+        group(&mut dxf, 8, "SECTION");
+    }
+
+    let (cx, cy) = point(section.x_bend, section.y_bend);
+    dxf_line(
+        &mut dxf,
+        "CENTERLINES",
+        (-offset / 2.0, cy),
+        (width + offset / 2.0, cy),
+    );
+    dxf_line(
+        &mut dxf,
+        "CENTERLINES",
+        (cx, -offset / 2.0),
+        (cx, height + offset / 2.0),
+    );
+    group(&mut dxf, 0, "CIRCLE");
+    group(&mut dxf, 8, "CENTERLINES");
+    group(&mut dxf, 10, cx);
+    group(&mut dxf, 20, cy);
+    group(&mut dxf, 40, text_height / 2.0);
+
+    dxf_line(
+        &mut dxf,
+        "DIMENSIONS",
+        (0.0, height + offset),
+        (width, height + offset),
+    );
+    dxf_text(
+        &mut dxf,
+        "DIMENSIONS",
+        (width / 2.0, height + offset + text_height / 2.0),
+        text_height,
+        &format!("{:.4}", width),
+    );
+    dxf_line(&mut dxf, "DIMENSIONS", (-offset, 0.0), (-offset, height));
+    dxf_text(
+        &mut dxf,
+        "DIMENSIONS",
+        (-offset - 4.0 * text_height, height / 2.0),
+        text_height,
+        &format!("{:.4}", height),
+    );
+    for (k, (plate, thickness)) in callouts(plates).iter().enumerate() {
+        dxf_text(
+            &mut dxf,
+            "DIMENSIONS",
+            (width + offset, height - 2.0 * text_height * k as f64),
+            text_height,
+            &format!(
+                "t {:.4} {} at ({:.4}, {:.4})",
+                thickness,
+                match plate.flange {
+                    true => "flange",
+                    false => "web",
+                },
+                point(plate.x, plate.y).0,
+                point(plate.x, plate.y).1
+            ),
+        );
+    }
+
+    group(&mut dxf, 0, "ENDSEC");
+    group(&mut dxf, 0, "EOF");
+    return fs::write(path, dxf)
+        .map_err(|e| format!("Cannot write drawing {}: {}", path.display(), e));
+}
+
+// Cross-section of a beam as `<dir>/<shape>_section.svg` and `.dxf`, with the neutral axes at room temperature.
+pub fn write_beam(dir: &str, beam: Beams) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Cannot create drawing directory {}: {}", dir, e))?;
+    let (title, plates, section) = match beam {
+        Beams::R(a) => ("Rectangular Beam", a.plates(), a.section(ROOM_TEMPERATURE)),
+        Beams::T(a) => ("T Beam", a.plates(), a.section(ROOM_TEMPERATURE)),
+        Beams::I(a) => ("I Beam", a.plates(), a.section(ROOM_TEMPERATURE)),
+    };
+    let path = Path::new(dir).join(format!("{}_section", beam.shape()));
+    return write_svg(&path.with_extension("svg"), title, &plates, &section)
+        .and_then(|_| write_dxf(&path.with_extension("dxf"), &plates, &section));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beams::Material;
+
+    #[test]
+    fn sections_without_area_are_not_drawn() {
+        let plate = Plate {
+            x: 1.0,
+            y: 1.0,
+            width: 0.0,
+            height: 2.0,
+            material: Material::Steel1018,
+            flange: false,
+        };
+        let section = Section {
+            modulus: 1.0,
+            x_bend: 1.0,
+            y_bend: 1.0,
+            ix: 1.0,
+            iy: 1.0,
+        };
+        let path = std::env::temp_dir().join("degenerate_section.dxf");
+        let error = write_dxf(&path, &[plate], &section).unwrap_err();
+        assert!(error.starts_with("Cannot draw a section"), "{}", error);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,527 @@
+use crate::constraints::{self, Evaluation};
+
+// Analysis outputs a scoring expression can refer to, in the order their values are handed to `Spec::evaluate`.
+// Stresses are the peak tension and compression (psi, compression negative), ply margins the lowest first ply failure
+// margins of safety of the laminate plates.
+pub const VARIABLES: [&str; 12] = [
+    "length",
+    "height",
+    "width",
+    "weight",
+    "cost",
+    "fos",
+    "deflection",
+    "flight_hours",
+    "tension",
+    "compression",
+    "ply_margin_tension",
+    "ply_margin_compression",
+];
+
+// Crack growth outputs, there with --damage-tolerance. Lengths in inches, times in flight hours.
+pub const DAMAGE_TOLERANCE_VARIABLES: [&str; 3] =
+    ["critical_crack", "hours_to_critical", "inspection_interval"];
+
+// Every name a scoring expression can use: VARIABLES, then fos_<case> and deflection_<case> for each load case, then
+// DAMAGE_TOLERANCE_VARIABLES when crack growth is analysed.
+pub fn variables(load_cases: &[String], damage_tolerance: bool) -> Vec<String> {
+    let mut names: Vec<String> = VARIABLES.iter().map(|name| name.to_string()).collect();
+    for case in load_cases {
+        names.push(format!("fos_{}", case));
+        names.push(format!("deflection_{}", case));
+    }
+    if damage_tolerance {
+        names.extend(
+            DAMAGE_TOLERANCE_VARIABLES
+                .iter()
+                .map(|name| name.to_string()),
+        );
+    }
+    return names;
+}
+
+// A name the tokenizer reads as one word, a letter or underscore followed by letters, digits and underscores.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    return chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+}
+
+const FUNCTIONS: [(&str, usize); 4] = [("abs", 1), ("sqrt", 1), ("min", 2), ("max", 2)];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    // Two character symbols first so <= isn't read as <.
+    const SYMBOLS: [&str; 14] = [
+        "<=", ">=", "<", ">", "+", "-", "*", "/", "^", "(", ")", "[", "]", ",",
+    ];
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, as in 1e5 or 2.5E-3.
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let sign =
+                    (i + 1 < chars.len() && (chars[i + 1] == '-' || chars[i + 1] == '+')) as usize;
+                if i + 1 + sign < chars.len() && chars[i + 1 + sign].is_ascii_digit() {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let number: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(
+                number
+                    .parse::<f64>()
+                    .map_err(|e| format!("Bad number {}: {}", number, e))?,
+            ));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or(format!("Unexpected character {} in {}", c, text))?;
+            tokens.push(Token::Symbol(symbol));
+            i += symbol.len();
+        }
+    }
+    return Ok(tokens);
+}
+
+// Arithmetic over the analysis outputs.
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Number(f64),
+    Variable(usize),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(&'static str, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn evaluate(&self, values: &[f64]) -> f64 {
+        return match self {
+            Expr::Number(value) => *value,
+            Expr::Variable(index) => values[*index],
+            Expr::Negate(operand) => -operand.evaluate(values),
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.evaluate(values), right.evaluate(values));
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => f64::powf(left, right),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args: Vec<f64> = args.iter().map(|arg| arg.evaluate(values)).collect();
+                match *name {
+                    "abs" => args[0].abs(),
+                    "sqrt" => args[0].sqrt(),
+                    "min" => args[0].min(args[1]),
+                    _ => args[0].max(args[1]),
+                }
+            }
+        };
+    }
+}
+
+// A requirement on the analysis outputs, scored by how far outside it a design is.
+#[derive(Clone, Debug)]
+pub enum Requirement {
+    Within(Expr, Expr, Expr),
+    AtMost(Expr, Expr),
+    AtLeast(Expr, Expr),
+}
+
+impl Requirement {
+    pub fn violation(&self, values: &[f64]) -> f64 {
+        return match self {
+            Requirement::Within(value, min, max) => constraints::outside(
+                value.evaluate(values),
+                min.evaluate(values),
+                max.evaluate(values),
+            ),
+            Requirement::AtMost(value, max) => {
+                constraints::above(value.evaluate(values), max.evaluate(values))
+            }
+            Requirement::AtLeast(value, min) => {
+                constraints::below(value.evaluate(values), min.evaluate(values))
+            }
+        };
+    }
+}
+
+// `minimize|maximize <expression> [subject to <requirement>, ...]`, where a requirement is `<expression> in [<min>,
+// <max>]`, `<expression> <= <expression>` or `<expression> >= <expression>`.
+#[derive(Clone, Debug)]
+pub struct Spec {
+    pub text: String,
+    maximize: bool,
+    objective: Expr,
+    requirements: Vec<Requirement>,
+}
+
+impl Spec {
+    // `variables` are the names the expressions can use, in the order of the values given to `evaluate`.
+    pub fn parse(text: &str, variables: &[String]) -> Result<Spec, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            variables,
+        };
+        let maximize = match parser.next() {
+            Some(Token::Name(name)) if name == "minimize" => false,
+            Some(Token::Name(name)) if name == "maximize" => true,
+            _ => {
+                return Err(format!(
+                    "Scoring should start with minimize or maximize: {}",
+                    text
+                ))
+            }
+        };
+        let objective = parser.expression()?;
+        let mut requirements = Vec::new();
+        if parser.peek().is_some() {
+            parser.keyword("subject")?;
+            parser.keyword("to")?;
+            requirements.push(parser.requirement()?);
+            while parser.eat_symbol(",") || parser.eat_keyword("and") {
+                requirements.push(parser.requirement()?);
+            }
+        }
+        if let Some(token) = parser.peek() {
+            return Err(format!("Unexpected {:?} in {}", token, text));
+        }
+        return Ok(Spec {
+            text: String::from(text),
+            maximize,
+            objective,
+            requirements,
+        });
+    }
+
+    // Objective to maximize and total violation of the requirements, given values in the order of the variables.
+    pub fn evaluate(&self, values: &[f64]) -> Evaluation {
+        let objective = self.objective.evaluate(values);
+        let violation = self
+            .requirements
+            .iter()
+            .map(|requirement| requirement.violation(values))
+            .sum();
+        return Evaluation {
+            objective: match self.maximize {
+                true => objective,
+                false => -objective,
+            },
+            violation,
+        };
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a [String],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position);
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        return token;
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if let Some(Token::Symbol(s)) = self.peek() {
+            if *s == symbol {
+                self.position += 1;
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if let Some(Token::Name(name)) = self.peek() {
+            if name == keyword {
+                self.position += 1;
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn symbol(&mut self, symbol: &str) -> Result<(), String> {
+        return match self.eat_symbol(symbol) {
+            true => Ok(()),
+            false => Err(format!("Expected {} but found {:?}", symbol, self.peek())),
+        };
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), String> {
+        return match self.eat_keyword(keyword) {
+            true => Ok(()),
+            false => Err(format!("Expected {} but found {:?}", keyword, self.peek())),
+        };
+    }
+
+    fn requirement(&mut self) -> Result<Requirement, String> {
+        let value = self.expression()?;
+        if self.eat_keyword("in") {
+            self.symbol("[")?;
+            let min = self.expression()?;
+            self.symbol(",")?;
+            let max = self.expression()?;
+            self.symbol("]")?;
+            return Ok(Requirement::Within(value, min, max));
+        }
+        // Strict and non-strict comparisons are the same requirement on continuous outputs.
+        if self.eat_symbol("<=") || self.eat_symbol("<") {
+            return Ok(Requirement::AtMost(value, self.expression()?));
+        }
+        if self.eat_symbol(">=") || self.eat_symbol(">") {
+            return Ok(Requirement::AtLeast(value, self.expression()?));
+        }
+        return Err(format!("Expected in, <= or >= but found {:?}", self.peek()));
+    }
+
+    // expression = term { (+|-) term }
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat_symbol("+") {
+                '+'
+            } else if self.eat_symbol("-") {
+                '-'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    // term = unary { (*|/) unary }
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat_symbol("*") {
+                '*'
+            } else if self.eat_symbol("/") {
+                '/'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    // unary = -unary | power
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        return self.power();
+    }
+
+    // power = atom [^ unary], right associative
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.eat_symbol("^") {
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        return Ok(base);
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        return match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Symbol("(")) => {
+                let inner = self.expression()?;
+                self.symbol(")")?;
+                Ok(inner)
+            }
+            Some(Token::Name(name)) => {
+                if let Some(index) = self.variables.iter().position(|v| *v == name) {
+                    return Ok(Expr::Variable(index));
+                }
+                let (function, arity) =
+                    FUNCTIONS.iter().find(|(f, _)| *f == name).ok_or(format!(
+                        "Unknown name {}, expected one of {} or a function {}",
+                        name,
+                        self.variables.join(", "),
+                        FUNCTIONS
+                            .iter()
+                            .map(|(f, _)| *f)
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    ))?;
+                self.symbol("(")?;
+                let mut args = vec![self.expression()?];
+                while self.eat_symbol(",") {
+                    args.push(self.expression()?);
+                }
+                self.symbol(")")?;
+                if args.len() != *arity {
+                    return Err(format!("{} takes {} arguments", function, arity));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            token => Err(format!("Unexpected {:?}", token)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        return variables(&[String::from("cold")], false);
+    }
+
+    // Objective of `maximize <text>` with length 2 and every other output 0.
+    fn value(text: &str) -> f64 {
+        let spec = Spec::parse(&format!("maximize {}", text), &names()).unwrap();
+        let mut values = vec![0.0; names().len()];
+        values[0] = 2.0;
+        return spec.evaluate(&values).objective;
+    }
+
+    fn error(text: &str) -> String {
+        return Spec::parse(text, &names()).unwrap_err();
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        assert_eq!(value("1 + 2 * 3"), 7.0);
+        assert_eq!(value("(1 + 2) * 3"), 9.0);
+        assert_eq!(value("2 * 3 ^ 2"), 18.0);
+        assert_eq!(value("5 - 2 - 1"), 2.0);
+        assert_eq!(value("8 / 4 / 2"), 1.0);
+        assert_eq!(
+            value("length * 1.5e1 + min(length, 1) - sqrt(abs(-16))"),
+            27.0
+        );
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_power() {
+        assert_eq!(value("-2 ^ 2"), -4.0);
+        assert_eq!(value("2 ^ -1"), 0.5);
+        assert_eq!(value("2 * -3"), -6.0);
+        assert_eq!(value("--length"), 2.0);
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(value("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(value("(2 ^ 3) ^ 2"), 64.0);
+    }
+
+    #[test]
+    fn requirements_add_their_violations() {
+        let spec = Spec::parse(
+            "minimize length subject to length in [1, 4], fos >= 1.5 and deflection_cold <= 70",
+            &names(),
+        )
+        .unwrap();
+        let mut values = vec![0.0; names().len()];
+        values[0] = 2.0;
+        values[5] = 1.5;
+        values[13] = 70.0;
+        let evaluation = spec.evaluate(&values);
+        assert_eq!(evaluation.objective, -2.0);
+        assert_eq!(evaluation.violation, 0.0);
+
+        // Length 5 misses its maximum of 4 by a quarter, fos 1.2 its minimum of 1.5 by a fifth.
+        values[0] = 5.0;
+        values[5] = 1.2;
+        let violation = spec.evaluate(&values).violation;
+        assert!((violation - 0.45).abs() < 1e-12, "violation {}", violation);
+    }
+
+    #[test]
+    fn variables_cover_load_cases_and_damage_tolerance() {
+        let names = variables(&[String::from("hot"), String::from("cold")], true);
+        assert_eq!(
+            names.len(),
+            VARIABLES.len() + 4 + DAMAGE_TOLERANCE_VARIABLES.len()
+        );
+        assert_eq!(names[VARIABLES.len()], "fos_hot");
+        assert_eq!(names[VARIABLES.len() + 3], "deflection_cold");
+        assert_eq!(names.last().unwrap(), "inspection_interval");
+        assert!(Spec::parse("maximize inspection_interval", &names).is_ok());
+    }
+
+    #[test]
+    fn identifiers_are_what_the_tokenizer_reads_as_names() {
+        for name in ["hot", "_1g", "Hot_Day2"] {
+            assert!(is_identifier(name), "{}", name);
+        }
+        for name in ["", "1g", "hot-day", "hot day", "cold.1"] {
+            assert!(!is_identifier(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn errors_say_what_went_wrong() {
+        assert!(error("length").starts_with("Scoring should start with minimize or maximize"));
+        assert!(
+            error("maximize lenght").starts_with("Unknown name lenght, expected one of length,")
+        );
+        assert!(
+            error("maximize inspection_interval").starts_with("Unknown name inspection_interval")
+        );
+        assert_eq!(error("maximize (1 + 2"), "Expected ) but found None");
+        assert_eq!(error("maximize min(1)"), "min takes 2 arguments");
+        assert_eq!(
+            error("maximize 1 $ 2"),
+            "Unexpected character $ in maximize 1 $ 2"
+        );
+        assert_eq!(
+            error("maximize 1 2"),
+            "Expected subject but found Some(Number(2.0))"
+        );
+        assert_eq!(
+            error("maximize 1 subject length"),
+            "Expected to but found Some(Name(\"length\"))"
+        );
+        assert_eq!(
+            error("maximize 1 subject to length"),
+            "Expected in, <= or >= but found None"
+        );
+        assert_eq!(
+            error("maximize 1 subject to length in [1 2]"),
+            "Expected , but found Some(Number(2.0))"
+        );
+    }
+}
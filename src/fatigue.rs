@@ -0,0 +1,281 @@
+use std::fs;
+
+// Recorded load history: time (s) against vertical load factor n_z.
+pub struct LoadHistory {
+    pub time: Vec<f64>,
+    pub load_factor: Vec<f64>,
+}
+
+// One rainflow cycle in load factor units. Half cycles have a count of 0.5.
+#[derive(Copy, Clone)]
+pub struct Cycle {
+    pub range: f64,
+    pub mean: f64,
+    pub count: f64,
+}
+
+// Cycle spectrum for one repeat of a load history and the flight time it represents.
+pub struct LoadSpectrum {
+    pub source: String,
+    pub cycles: Vec<Cycle>,
+    pub duration_hours: f64,
+}
+
+// Reads a two column CSV of time vs n_z. A header line is allowed.
+pub fn read_load_history(path: &str) -> Result<LoadHistory, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read load history {}: {}", path, e))?;
+    let mut history = LoadHistory {
+        time: Vec::new(),
+        load_factor: Vec::new(),
+    };
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let parsed = match fields.len() {
+            2 => fields[0]
+                .parse::<f64>()
+                .and_then(|t| fields[1].parse::<f64>().map(|n| (t, n))),
+            _ => return Err(format!("{}:{}: expected `time, n_z`", path, index + 1)),
+        };
+        match parsed {
+            Ok((t, n)) => {
+                history.time.push(t);
+                history.load_factor.push(n);
+            }
+            Err(_) if index == 0 => continue,
+            Err(e) => return Err(format!("{}:{}: {}", path, index + 1, e)),
+        }
+    }
+
+    if history.time.len() < 2 {
+        return Err(format!("{}: load history needs at least two samples", path));
+    }
+    return Ok(history);
+}
+
+// Peaks and valleys of a signal, including the first and last samples.
+fn reversals(signal: &[f64]) -> Vec<f64> {
+    let mut points: Vec<f64> = Vec::new();
+    for &value in signal {
+        if points.last() == Some(&value) {
+            continue;
+        }
+        if points.len() >= 2 {
+            let a = points[points.len() - 2];
+            let b = points[points.len() - 1];
+            // Drop the middle point when the signal keeps going the same way.
+            if (b - a) * (value - b) > 0.0 {
+                points.pop();
+            }
+        }
+        points.push(value);
+    }
+    return points;
+}
+
+// Three point rainflow counting (ASTM E1049-85).
+pub fn rainflow(signal: &[f64]) -> Vec<Cycle> {
+    let mut cycles: Vec<Cycle> = Vec::new();
+    let mut stack: Vec<f64> = Vec::new();
+
+    for point in reversals(signal) {
+        stack.push(point);
+        while stack.len() >= 3 {
+            let n = stack.len();
+            let x = (stack[n - 1] - stack[n - 2]).abs();
+            let y = (stack[n - 2] - stack[n - 3]).abs();
+            if x < y {
+                break;
+            }
+            let mean = (stack[n - 2] + stack[n - 3]) / 2.0;
+            if n == 3 {
+                // Range y contains the starting point, count it as a half cycle.
+                cycles.push(Cycle {
+                    range: y,
+                    mean,
+                    count: 0.5,
+                });
+                stack.remove(0);
+            } else {
+                cycles.push(Cycle {
+                    range: y,
+                    mean,
+                    count: 1.0,
+                });
+                stack.remove(n - 2);
+                stack.remove(n - 3);
+            }
+        }
+    }
+
+    // Whatever is left never closed, so the residue counts as half cycles.
+    for pair in stack.windows(2) {
+        cycles.push(Cycle {
+            range: (pair[1] - pair[0]).abs(),
+            mean: (pair[1] + pair[0]) / 2.0,
+            count: 0.5,
+        });
+    }
+    return cycles;
+}
+
+impl LoadSpectrum {
+    pub fn from_history(source: &str, history: &LoadHistory) -> LoadSpectrum {
+        let duration = history.time[history.time.len() - 1] - history.time[0];
+        return LoadSpectrum {
+            source: String::from(source),
+            cycles: rainflow(&history.load_factor),
+            duration_hours: duration / 3600.0,
+        };
+    }
+
+    pub fn load(path: &str) -> Result<LoadSpectrum, String> {
+        let history = read_load_history(path)?;
+        let spectrum = LoadSpectrum::from_history(path, &history);
+        if spectrum.duration_hours <= 0.0 {
+            return Err(format!("{}: time must increase through the history", path));
+        }
+        // A constant or one way history has no peak or valley between its ends, so no fatigue cycle would close
+        // and the life would come out infinite.
+        if reversals(&history.load_factor).len() < 3 {
+            return Err(format!(
+                "{}: load history never reverses, so it has no fatigue cycles",
+                path
+            ));
+        }
+        return Ok(spectrum);
+    }
+}
+
+// How the mean stress of a cycle is folded into an equivalent fully reversed amplitude.
+#[derive(Copy, Clone)]
+pub enum MeanStressCorrection {
+    Goodman,
+    Gerber,
+    Soderberg,
+    Walker(f64),
+}
+
+impl Default for MeanStressCorrection {
+    fn default() -> MeanStressCorrection {
+        return MeanStressCorrection::Goodman;
+    }
+}
+
+impl MeanStressCorrection {
+    // Accepts goodman, gerber, soderberg, walker or walker:<gamma>.
+    pub fn parse(name: &str) -> Result<MeanStressCorrection, String> {
+        let lower = name.to_lowercase();
+        let mut parts = lower.splitn(2, ':');
+        return match (parts.next(), parts.next()) {
+            (Some("goodman"), None) => Ok(MeanStressCorrection::Goodman),
+            (Some("gerber"), None) => Ok(MeanStressCorrection::Gerber),
+            (Some("soderberg"), None) => Ok(MeanStressCorrection::Soderberg),
+            (Some("walker"), None) => Ok(MeanStressCorrection::Walker(0.5)),
+            (Some("walker"), Some(gamma)) => gamma
+                .parse::<f64>()
+                .map(MeanStressCorrection::Walker)
+                .map_err(|e| format!("Bad Walker exponent {}: {}", gamma, e)),
+            _ => Err(format!("Unknown mean stress correction {}", name)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            MeanStressCorrection::Goodman => String::from("Goodman"),
+            MeanStressCorrection::Gerber => String::from("Gerber"),
+            MeanStressCorrection::Soderberg => String::from("Soderberg"),
+            MeanStressCorrection::Walker(gamma) => format!("Walker (gamma {})", gamma),
+        };
+    }
+
+    // Equivalent amplitude in the same units as the inputs, or None when the cycle fails outright.
+    pub fn equivalent_amplitude(
+        &self,
+        amplitude: f64,
+        mean: f64,
+        fatigue_strength: f64,
+        yield_strength: f64,
+    ) -> Option<f64> {
+        let amplitude = amplitude.abs();
+        let knockdown = match self {
+            MeanStressCorrection::Goodman => 1.0 - mean / fatigue_strength,
+            MeanStressCorrection::Gerber => 1.0 - f64::powf(mean / fatigue_strength, 2.0),
+            MeanStressCorrection::Soderberg => 1.0 - mean / yield_strength,
+            MeanStressCorrection::Walker(gamma) => {
+                let max_stress = mean + amplitude;
+                if max_stress <= 0.0 {
+                    return Some(0.0);
+                }
+                return Some(f64::powf(max_stress, 1.0 - gamma) * f64::powf(amplitude, *gamma));
+            }
+        };
+        if knockdown <= 0.0 {
+            return None;
+        }
+        return Some(amplitude / knockdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Total count of each range, sorted by range.
+    fn counts(cycles: &[Cycle]) -> Vec<(f64, f64)> {
+        let mut counts: Vec<(f64, f64)> = Vec::new();
+        for cycle in cycles {
+            match counts.iter_mut().find(|(range, _)| *range == cycle.range) {
+                Some((_, count)) => *count += cycle.count,
+                None => counts.push((cycle.range, cycle.count)),
+            }
+        }
+        counts.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        return counts;
+    }
+
+    #[test]
+    fn rainflow_matches_astm_e1049_example() {
+        let history = [-2.0, 1.0, -3.0, 5.0, -1.0, 3.0, -4.0, 4.0, -2.0];
+        assert_eq!(
+            counts(&rainflow(&history)),
+            vec![(3.0, 0.5), (4.0, 1.5), (6.0, 0.5), (8.0, 1.0), (9.0, 0.5)]
+        );
+    }
+
+    #[test]
+    fn reversals_drop_intermediate_and_repeated_points() {
+        assert_eq!(
+            reversals(&[0.0, 1.0, 2.0, 2.0, 1.0, 3.0, 3.0]),
+            vec![0.0, 2.0, 1.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn rainflow_counts_every_reversal_once() {
+        let history = [1.0, -1.5, 2.0, -0.5, 1.0, -2.0, 0.5, -1.0, 2.5, 0.0];
+        let half_cycles: f64 = rainflow(&history).iter().map(|c| 2.0 * c.count).sum();
+        assert_eq!(half_cycles, (reversals(&history).len() - 1) as f64);
+    }
+
+    #[test]
+    fn histories_without_reversals_are_rejected() {
+        for (name, loads) in [("constant", "1, 1, 1"), ("monotonic", "0, 1, 2")] {
+            let path = std::env::temp_dir().join(format!("load_history_{}.csv", name));
+            let rows: Vec<String> = loads
+                .split(", ")
+                .enumerate()
+                .map(|(t, n)| format!("{}, {}", t, n))
+                .collect();
+            fs::write(&path, rows.join("\n")).unwrap();
+            let result = LoadSpectrum::load(path.to_str().unwrap());
+            fs::remove_file(&path).unwrap();
+            assert!(result.is_err(), "{} history was accepted", name);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::beams::WEIGHT_MAX;
+use float_ord::FloatOrd;
+use rsgenetic::pheno::Fitness;
+use std::fmt;
+
+// Score of a design, higher is better. NaN counts as the worst possible score so a broken design never wins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BeamFitness(FloatOrd<f64>);
+
+impl BeamFitness {
+    pub fn new(score: f64) -> BeamFitness {
+        return match score.is_nan() {
+            true => BeamFitness(FloatOrd(f64::NEG_INFINITY)),
+            false => BeamFitness(FloatOrd(score)),
+        };
+    }
+
+    pub fn value(&self) -> f64 {
+        return self.0 .0;
+    }
+}
+
+impl fmt::Display for BeamFitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.value());
+    }
+}
+
+impl Fitness for BeamFitness {
+    fn zero() -> BeamFitness {
+        return BeamFitness::new(0.0);
+    }
+
+    fn abs_diff(&self, other: &BeamFitness) -> BeamFitness {
+        return BeamFitness::new((self.value() - other.value()).abs());
+    }
+}
+
+// What the optimizer is after, before the requirements are considered. Margin is the factor of safety, which the
+// caller stops counting at the top of its window.
+#[derive(Copy, Clone)]
+pub enum Objective {
+    Weight,
+    Cost,
+    Margin,
+    // Weight and cost count against, margin for.
+    WeightedSum { weight: f64, cost: f64, margin: f64 },
+}
+
+impl Default for Objective {
+    // Weighting the original scoring used: a pound of weight is worth three dollars of cost, and a unit of
+    // factor of safety is worth the whole weight allowance.
+    fn default() -> Objective {
+        return Objective::WeightedSum {
+            weight: 1.0,
+            cost: 1.0 / 3.0,
+            margin: WEIGHT_MAX,
+        };
+    }
+}
+
+impl Objective {
+    pub fn parse(text: &str) -> Result<Objective, String> {
+        let parts: Vec<&str> = text.split(':').collect();
+        return match parts[..] {
+            ["weight"] => Ok(Objective::Weight),
+            ["cost"] => Ok(Objective::Cost),
+            ["margin"] => Ok(Objective::Margin),
+            ["weighted", weight, cost, margin] => {
+                let parse = |field: &str| {
+                    field
+                        .parse::<f64>()
+                        .map_err(|e| format!("Bad objective weight {}: {}", field, e))
+                };
+                Ok(Objective::WeightedSum {
+                    weight: parse(weight)?,
+                    cost: parse(cost)?,
+                    margin: parse(margin)?,
+                })
+            }
+            _ => Err(format!("Unknown objective {}", text)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            Objective::Weight => String::from("minimize weight"),
+            Objective::Cost => String::from("minimize cost"),
+            Objective::Margin => String::from("maximize margin"),
+            Objective::WeightedSum {
+                weight,
+                cost,
+                margin,
+            } => format!(
+                "weighted sum (weight {}, cost {}, margin {})",
+                weight, cost, margin
+            ),
+        };
+    }
+
+    // Higher is better. Weight and cost can't help below zero, so no design scores its way out of its requirements.
+    pub fn value(&self, weight: f64, cost: f64, margin: f64) -> f64 {
+        let weight = weight.max(0.0);
+        let cost = cost.max(0.0);
+        return match *self {
+            Objective::Weight => -weight,
+            Objective::Cost => -cost,
+            Objective::Margin => margin,
+            Objective::WeightedSum {
+                weight: weight_weight,
+                cost: cost_weight,
+                margin: margin_weight,
+            } => margin_weight * margin - weight_weight * weight - cost_weight * cost,
+        };
+    }
+}
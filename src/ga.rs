@@ -0,0 +1,159 @@
+use crate::rng;
+use crate::stopping::StopReason;
+use rand::Rng;
+use rsgenetic::pheno::{Fitness, Phenotype};
+use rsgenetic::sim::select::Selector;
+
+// Continuous design variables of a phenotype, for statistics and the real-coded operators.
+pub trait Genes: Sized {
+    fn genes(&self) -> Vec<f64>;
+    // Same phenotype with its genes replaced, in the order `genes` gives them.
+    fn with_genes(&self, genes: &[f64]) -> Self;
+    // Names of the genes in the same order, for tables and the command line.
+    fn gene_names(&self) -> Vec<&'static str>;
+}
+
+// Winner of a simulation, with how many generations it ran and why it stopped.
+pub struct Outcome<T> {
+    pub best: T,
+    pub generations: u64,
+    pub stop: StopReason,
+}
+
+// The generation step of rsgenetic's Simulator, with the kill off drawn from this thread's seeded generator so a
+// seeded run repeats exactly. Generations continue on from `first_generation`, which is non-zero when resuming a
+// checkpoint. The best `elites` individuals are never killed off. `report` sees the population after every generation and can end the run early.
+pub fn evolve<T, F>(
+    population: &mut Vec<T>,
+    selector: &dyn Selector<T, F>,
+    elites: usize,
+    first_generation: u64,
+    iterations: u64,
+    report: &mut dyn FnMut(u64, &[T]) -> Option<StopReason>,
+) -> Result<Outcome<T>, String>
+where
+    T: Phenotype<F>,
+    F: Fitness,
+{
+    if population.is_empty() {
+        return Err(String::from(
+            "Tried to run a simulator without a population",
+        ));
+    }
+    let mut generations = first_generation;
+    let mut stop = StopReason::Iterations;
+    for generation in first_generation + 1..=iterations {
+        let mut children: Vec<T> = selector
+            .select(population)?
+            .iter()
+            .map(|&(a, b)| a.crossover(b).mutate())
+            .collect();
+        kill_off(population, children.len(), elites);
+        population.append(&mut children);
+        generations = generation;
+        if let Some(reason) = report(generation, population) {
+            stop = reason;
+            break;
+        }
+    }
+    return Ok(Outcome {
+        best: population
+            .iter()
+            .max_by_key(|x| x.fitness())
+            .unwrap()
+            .clone(),
+        generations,
+        stop,
+    });
+}
+
+// Kill off phenotypes using stochastic universal sampling. Elites sit out and rejoin afterwards.
+fn kill_off<T, F>(population: &mut Vec<T>, count: usize, elites: usize)
+where
+    T: Phenotype<F>,
+    F: Fitness,
+{
+    let mut saved: Vec<T> = Vec::new();
+    if elites > 0 {
+        let scores: Vec<F> = population.iter().map(|x| x.fitness()).collect();
+        let mut order: Vec<usize> = (0..population.len()).collect();
+        order.sort_by(|&a, &b| scores[b].cmp(&scores[a]));
+        let mut keep: Vec<usize> = order.into_iter().take(elites).collect();
+        // Highest index first so each swap_remove leaves the rest where they were.
+        keep.sort_unstable_by(|a, b| b.cmp(a));
+        saved = keep
+            .into_iter()
+            .map(|i| population.swap_remove(i))
+            .collect();
+    }
+    let count = count.min(population.len());
+    if let Some(ratio) = population.len().checked_div(count) {
+        let mut i = rng::thread_rng().gen_range(0..population.len());
+        for _ in 0..count {
+            population.swap_remove(i);
+            if population.is_empty() {
+                break;
+            }
+            i += ratio;
+            i %= population.len();
+        }
+    }
+    population.append(&mut saved);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fitness::BeamFitness;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Score(f64);
+
+    impl Phenotype<BeamFitness> for Score {
+        fn fitness(&self) -> BeamFitness {
+            return BeamFitness::new(self.0);
+        }
+
+        fn crossover(&self, other: &Score) -> Score {
+            return Score((self.0 + other.0) / 2.0);
+        }
+
+        fn mutate(&self) -> Score {
+            return self.clone();
+        }
+    }
+
+    #[test]
+    fn elites_survive_the_kill_off() {
+        rng::seed(1);
+        let mut population: Vec<Score> = [3.0, 9.0, 1.0, 7.0, 5.0, 0.0, 8.0, 2.0, 6.0, 4.0]
+            .iter()
+            .map(|&s| Score(s))
+            .collect();
+        kill_off(&mut population, 8, 2);
+        population.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(population, vec![Score(8.0), Score(9.0)]);
+    }
+
+    #[test]
+    fn elitism_never_loses_the_best() {
+        rng::seed(1);
+        // Children average their parents, so without elitism the best is soon bred out.
+        let mut population: Vec<Score> = (0..20).map(|i| Score(i as f64)).collect();
+        let selector = rsgenetic::sim::select::UnstableMaximizeSelector::new(8);
+        let mut best = Vec::new();
+        evolve(
+            &mut population,
+            &selector,
+            1,
+            0,
+            20,
+            &mut |_, population| {
+                best.push(population.iter().map(|x| x.0).fold(f64::MIN, f64::max));
+                return None;
+            },
+        )
+        .unwrap();
+        assert!(best.iter().all(|&b| b == 19.0));
+    }
+}
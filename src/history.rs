@@ -0,0 +1,123 @@
+use crate::checkpoint::Record;
+use crate::config;
+use crate::constraints::{Evaluate, Evaluation};
+use crate::fitness::BeamFitness;
+use crate::ga::Genes;
+use std::fs;
+use std::path::Path;
+
+// Population statistics for one generation of a simulation.
+#[derive(Copy, Clone)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub best: f64,
+    pub mean: f64,
+    pub worst: f64,
+    pub feasible: usize,
+    pub best_feasible: bool,
+    // Mean coefficient of variation of the genes across the population, 0 once it has collapsed to one design.
+    pub diversity: f64,
+}
+
+impl GenerationStats {
+    // Each individual is evaluated once, its fitness is the configured constraint handling of that evaluation.
+    pub fn from_population<T>(generation: u64, population: &[T]) -> GenerationStats
+    where
+        T: Genes + Evaluate,
+    {
+        let evaluations: Vec<Evaluation> = population.iter().map(|x| x.evaluation()).collect();
+        let scores: Vec<BeamFitness> = evaluations
+            .iter()
+            .map(|&e| BeamFitness::new(config::get().constraint_handling.fitness(e)))
+            .collect();
+        let feasible: Vec<bool> = evaluations.iter().map(|e| e.is_feasible()).collect();
+        let best = (0..scores.len()).max_by_key(|&index| scores[index]);
+        let genes: Vec<Vec<f64>> = population.iter().map(|x| x.genes()).collect();
+        return GenerationStats {
+            generation,
+            best: scores.iter().max().map_or(0.0, |s| s.value()),
+            mean: scores.iter().map(|s| s.value()).sum::<f64>() / scores.len().max(1) as f64,
+            worst: scores.iter().min().map_or(0.0, |s| s.value()),
+            feasible: feasible.iter().filter(|&&f| f).count(),
+            best_feasible: best.is_some_and(|index| feasible[index]),
+            diversity: diversity(&genes),
+        };
+    }
+}
+
+// Saved with each checkpoint, so a resumed run writes the whole convergence history and not only its own part.
+impl Record for GenerationStats {
+    fn record(&self) -> String {
+        return format!(
+            "{} {} {} {} {} {} {}",
+            self.generation,
+            self.best,
+            self.mean,
+            self.worst,
+            self.feasible,
+            self.best_feasible,
+            self.diversity
+        );
+    }
+
+    fn from_record(line: &str) -> Result<GenerationStats, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 7 {
+            return Err(format!("Expected 7 fields in generation {}", line));
+        }
+        let float = |field: &str| {
+            field
+                .parse::<f64>()
+                .map_err(|e| format!("Bad value {}: {}", field, e))
+        };
+        return Ok(GenerationStats {
+            generation: fields[0]
+                .parse::<u64>()
+                .map_err(|e| format!("Bad generation {}: {}", fields[0], e))?,
+            best: float(fields[1])?,
+            mean: float(fields[2])?,
+            worst: float(fields[3])?,
+            feasible: fields[4]
+                .parse::<usize>()
+                .map_err(|e| format!("Bad feasible count {}: {}", fields[4], e))?,
+            best_feasible: fields[5]
+                .parse::<bool>()
+                .map_err(|e| format!("Bad feasibility {}: {}", fields[5], e))?,
+            diversity: float(fields[6])?,
+        });
+    }
+}
+
+// Genomes can differ in length in a mixed shape population, each gene position is taken over those that have it.
+fn diversity(genes: &[Vec<f64>]) -> f64 {
+    let positions = genes.iter().map(|g| g.len()).max().unwrap_or(0);
+    let mut total = 0.0;
+    for position in 0..positions {
+        let values: Vec<f64> = genes
+            .iter()
+            .filter_map(|g| g.get(position).copied())
+            .collect();
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values.iter().map(|v| f64::powf(v - mean, 2.0)).sum::<f64>() / count;
+        if mean != 0.0 {
+            total += variance.sqrt() / mean.abs();
+        }
+    }
+    return match positions {
+        0 => 0.0,
+        _ => total / positions as f64,
+    };
+}
+
+pub fn write_csv(path: &Path, history: &[GenerationStats]) -> Result<(), String> {
+    let mut contents = String::from("generation,best,mean,worst,feasible,diversity\n");
+    for stats in history {
+        contents.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            stats.generation, stats.best, stats.mean, stats.worst, stats.feasible, stats.diversity
+        ));
+    }
+    return fs::write(path, contents)
+        .map_err(|e| format!("Cannot write convergence history {}: {}", path.display(), e));
+}
@@ -0,0 +1,270 @@
+use std::sync::OnceLock;
+
+// Unidirectional ply properties along (1) and across (2) the fibres. Moduli and strengths in psi.
+pub struct Ply {
+    pub e1: f64,
+    pub e2: f64,
+    pub g12: f64,
+    pub nu12: f64,
+    pub x_t: f64,
+    pub x_c: f64,
+    pub y_t: f64,
+    pub y_c: f64,
+    pub s: f64,
+}
+
+// Ply stack with angles (deg) measured from the beam axis, listed through the full thickness.
+pub struct Laminate {
+    pub ply: Ply,
+    pub angles: &'static [f64],
+    pub ply_thickness: f64,
+    pub density: f64,
+    pub cost: f64,
+    pub fatigue_exponent: f64,
+    cache: OnceLock<LaminateProperties>,
+}
+
+// Effective properties of the laminate loaded along the beam axis.
+#[derive(Copy, Clone)]
+pub struct LaminateProperties {
+    pub modulus: f64,
+    // First ply failure stress (psi) in tension and compression for each criterion.
+    pub tsai_wu: [f64; 2],
+    pub max_stress: [f64; 2],
+}
+
+#[derive(Copy, Clone)]
+pub enum PlyFailure {
+    TsaiWu,
+    MaxStress,
+}
+
+impl Default for PlyFailure {
+    fn default() -> PlyFailure {
+        return PlyFailure::TsaiWu;
+    }
+}
+
+impl PlyFailure {
+    pub fn parse(name: &str) -> Result<PlyFailure, String> {
+        return match name.to_lowercase().as_str() {
+            "tsai-wu" => Ok(PlyFailure::TsaiWu),
+            "max-stress" => Ok(PlyFailure::MaxStress),
+            _ => Err(format!("Unknown ply failure criterion {}", name)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        let name = match self {
+            PlyFailure::TsaiWu => "Tsai-Wu",
+            PlyFailure::MaxStress => "Max Stress",
+        };
+        return String::from(name);
+    }
+}
+
+const AS4_3501_6: Ply = Ply {
+    e1: 20.0e6,
+    e2: 1.36e6,
+    g12: 0.87e6,
+    nu12: 0.28,
+    x_t: 210.0e3,
+    x_c: 170.0e3,
+    y_t: 7.5e3,
+    y_c: 29.9e3,
+    s: 13.5e3,
+};
+
+const E_GLASS_EPOXY: Ply = Ply {
+    e1: 5.6e6,
+    e2: 1.2e6,
+    g12: 0.6e6,
+    nu12: 0.26,
+    x_t: 150.0e3,
+    x_c: 88.5e3,
+    y_t: 4.0e3,
+    y_c: 17.0e3,
+    s: 10.0e3,
+};
+
+pub static CARBON_EPOXY_QUASI_ISOTROPIC: Laminate = Laminate {
+    ply: AS4_3501_6,
+    angles: &[0.0, 45.0, -45.0, 90.0, 90.0, -45.0, 45.0, 0.0],
+    ply_thickness: 0.0052,
+    density: 0.058,
+    cost: 40.0,
+    fatigue_exponent: -0.04,
+    cache: OnceLock::new(),
+};
+
+pub static CARBON_EPOXY_SPAR_CAP: Laminate = Laminate {
+    ply: AS4_3501_6,
+    angles: &[
+        0.0, 0.0, 45.0, -45.0, 0.0, 90.0, 90.0, 0.0, -45.0, 45.0, 0.0, 0.0,
+    ],
+    ply_thickness: 0.0052,
+    density: 0.058,
+    cost: 40.0,
+    fatigue_exponent: -0.04,
+    cache: OnceLock::new(),
+};
+
+pub static GLASS_EPOXY_QUASI_ISOTROPIC: Laminate = Laminate {
+    ply: E_GLASS_EPOXY,
+    angles: &[0.0, 45.0, -45.0, 90.0, 90.0, -45.0, 45.0, 0.0],
+    ply_thickness: 0.007,
+    density: 0.072,
+    cost: 8.0,
+    fatigue_exponent: -0.08,
+    cache: OnceLock::new(),
+};
+
+// Reduced stiffness [Q11, Q22, Q12, Q66] in the ply axes.
+fn reduced_stiffness(ply: &Ply) -> [f64; 4] {
+    let nu21 = ply.nu12 * ply.e2 / ply.e1;
+    let denominator = 1.0 - ply.nu12 * nu21;
+    return [
+        ply.e1 / denominator,
+        ply.e2 / denominator,
+        ply.nu12 * ply.e2 / denominator,
+        ply.g12,
+    ];
+}
+
+// Ply stiffness rotated into the beam axes.
+fn transformed_stiffness(q: [f64; 4], angle: f64) -> [[f64; 3]; 3] {
+    let [q11, q22, q12, q66] = q;
+    let m = angle.to_radians().cos();
+    let n = angle.to_radians().sin();
+    let (m2, n2) = (m * m, n * n);
+    let qb11 = q11 * m2 * m2 + 2.0 * (q12 + 2.0 * q66) * m2 * n2 + q22 * n2 * n2;
+    let qb22 = q11 * n2 * n2 + 2.0 * (q12 + 2.0 * q66) * m2 * n2 + q22 * m2 * m2;
+    let qb12 = (q11 + q22 - 4.0 * q66) * m2 * n2 + q12 * (m2 * m2 + n2 * n2);
+    let qb66 = (q11 + q22 - 2.0 * q12 - 2.0 * q66) * m2 * n2 + q66 * (m2 * m2 + n2 * n2);
+    let qb16 = (q11 - q12 - 2.0 * q66) * m2 * m * n - (q22 - q12 - 2.0 * q66) * m * n2 * n;
+    let qb26 = (q11 - q12 - 2.0 * q66) * m * n2 * n - (q22 - q12 - 2.0 * q66) * m2 * m * n;
+    return [[qb11, qb12, qb16], [qb12, qb22, qb26], [qb16, qb26, qb66]];
+}
+
+fn invert(matrix: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let [[a, b, c], [d, e, f], [g, h, i]] = matrix;
+    let determinant = a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g);
+    return [
+        [
+            (e * i - f * h) / determinant,
+            (c * h - b * i) / determinant,
+            (b * f - c * e) / determinant,
+        ],
+        [
+            (f * g - d * i) / determinant,
+            (a * i - c * g) / determinant,
+            (c * d - a * f) / determinant,
+        ],
+        [
+            (d * h - e * g) / determinant,
+            (b * g - a * h) / determinant,
+            (a * e - b * d) / determinant,
+        ],
+    ];
+}
+
+// Load factor on a ply stress state that reaches failure.
+fn strength_ratio(ply: &Ply, stress: [f64; 3], criterion: PlyFailure) -> f64 {
+    let [s1, s2, t12] = stress;
+    return match criterion {
+        PlyFailure::TsaiWu => {
+            let f1 = 1.0 / ply.x_t - 1.0 / ply.x_c;
+            let f2 = 1.0 / ply.y_t - 1.0 / ply.y_c;
+            let f11 = 1.0 / (ply.x_t * ply.x_c);
+            let f22 = 1.0 / (ply.y_t * ply.y_c);
+            let f66 = 1.0 / (ply.s * ply.s);
+            let f12 = -0.5 * (f11 * f22).sqrt();
+            let a = f11 * s1 * s1 + f22 * s2 * s2 + f66 * t12 * t12 + 2.0 * f12 * s1 * s2;
+            let b = f1 * s1 + f2 * s2;
+            (-b + (b * b + 4.0 * a).sqrt()) / (2.0 * a)
+        }
+        PlyFailure::MaxStress => {
+            let limit = |value: f64, tension: f64, compression: f64| match value {
+                v if v > 0.0 => tension / v,
+                v if v < 0.0 => compression / -v,
+                _ => f64::INFINITY,
+            };
+            limit(s1, ply.x_t, ply.x_c)
+                .min(limit(s2, ply.y_t, ply.y_c))
+                .min(limit(t12.abs(), ply.s, ply.s))
+        }
+    };
+}
+
+impl Laminate {
+    pub fn thickness(&self) -> f64 {
+        return self.ply_thickness * self.angles.len() as f64;
+    }
+
+    pub fn properties(&self) -> LaminateProperties {
+        return *self.cache.get_or_init(|| self.classical_lamination());
+    }
+
+    pub fn first_ply_failure(&self, stress: f64, criterion: PlyFailure) -> f64 {
+        let properties = self.properties();
+        let strengths = match criterion {
+            PlyFailure::TsaiWu => properties.tsai_wu,
+            PlyFailure::MaxStress => properties.max_stress,
+        };
+        return match stress < 0.0 {
+            true => strengths[1],
+            false => strengths[0],
+        };
+    }
+
+    // Classical lamination theory for a membrane load along the beam axis.
+    fn classical_lamination(&self) -> LaminateProperties {
+        let q = reduced_stiffness(&self.ply);
+        let mut a_matrix = [[0.0; 3]; 3];
+        for &angle in self.angles {
+            let q_bar = transformed_stiffness(q, angle);
+            for row in 0..3 {
+                for col in 0..3 {
+                    a_matrix[row][col] += q_bar[row][col] * self.ply_thickness;
+                }
+            }
+        }
+        let compliance = invert(a_matrix);
+        let thickness = self.thickness();
+
+        // Mid-plane strains under a unit average stress, i.e. Nx = thickness.
+        let strain = [
+            compliance[0][0] * thickness,
+            compliance[1][0] * thickness,
+            compliance[2][0] * thickness,
+        ];
+        let mut tsai_wu = [f64::INFINITY; 2];
+        let mut max_stress = [f64::INFINITY; 2];
+        for &angle in self.angles {
+            let m = angle.to_radians().cos();
+            let n = angle.to_radians().sin();
+            let e1 = m * m * strain[0] + n * n * strain[1] + m * n * strain[2];
+            let e2 = n * n * strain[0] + m * m * strain[1] - m * n * strain[2];
+            let g12 =
+                -2.0 * m * n * strain[0] + 2.0 * m * n * strain[1] + (m * m - n * n) * strain[2];
+            let ply_stress = [q[0] * e1 + q[2] * e2, q[2] * e1 + q[1] * e2, q[3] * g12];
+            for (index, sign) in [1.0, -1.0].iter().enumerate() {
+                let signed = [
+                    sign * ply_stress[0],
+                    sign * ply_stress[1],
+                    sign * ply_stress[2],
+                ];
+                tsai_wu[index] =
+                    tsai_wu[index].min(strength_ratio(&self.ply, signed, PlyFailure::TsaiWu));
+                max_stress[index] =
+                    max_stress[index].min(strength_ratio(&self.ply, signed, PlyFailure::MaxStress));
+            }
+        }
+
+        return LaminateProperties {
+            modulus: 1.0 / (compliance[0][0] * thickness),
+            tsai_wu,
+            max_stress,
+        };
+    }
+}
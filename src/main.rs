@@ -1,18 +1,50 @@
+use duplicate::duplicate;
 use rand::prelude::*;
 use rsgenetic::pheno::*;
-use rsgenetic::sim::select::*;
-use rsgenetic::sim::seq::Simulator;
-use rsgenetic::sim::*;
+use std::path::Path;
 use std::string::String;
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::mpsc::{self, Sender};
 use std::thread;
+use std::time::{Duration, Instant};
 use strum::IntoEnumIterator;
 
+mod analysis;
 mod beams;
+mod checkpoint;
+mod config;
+mod constraints;
+mod crack;
+mod crossover;
+mod drawing;
+mod expression;
+mod fatigue;
+mod fitness;
+mod ga;
+mod history;
+mod laminate;
+mod mesh;
+mod mutation;
+mod optimizer;
+mod plot;
+mod refine;
+mod rng;
+mod selection;
+mod stopping;
+mod sweep;
 
-//Modify the scoring function to change that the algorithm considers good. Recompile with a release version so it is optimized, run, and wait. It will spit out the best beam it finds.
+//Pass --score (or modify the scoring function) to change that the algorithm considers good. Recompile with a release version so it is optimized, run, and wait. It will spit out the best beam it finds.
 
+use analysis::{
+    get_deflection, get_factor_of_safety, get_flight_hours, get_peak_stresses, get_ply_margins,
+};
 use beams::*;
+use checkpoint::{Checkpoint, Record, CHECKPOINT_GENERATIONS};
+use constraints::{Evaluate, Evaluation};
+use fitness::BeamFitness;
+use ga::Genes;
+use history::GenerationStats;
+use optimizer::Breeding;
+use stopping::{EarlyStopper, StopReason};
 //GA specs
 const POP_SIZE: u32 = 1000;
 const POP_SURVIVORS: usize = 100;
@@ -25,6 +57,9 @@ const HEIGHT: f64 = 34.0;
 const WIDTH_MIN: f64 = 22.0;
 const WIDTH_MAX: f64 = 38.0;
 
+// Plates can't be thinner than this, mutation bounds keep thicknesses above it.
+const THICKNESS_MIN: f64 = 0.01;
+
 //requirements
 const FOS_MAX: f64 = 2.2;
 const FOS_MIN: f64 = 1.4;
@@ -36,285 +71,324 @@ const PRICE_MAX: f64 = 500000.00;
 const FATIGUE_FLIGHT_HOURS_MAX: f64 = 500000.0;
 const FATIGUE_FLIGHT_HOURS_MIN: f64 = 42000.0;
 
-const WEIGHT_MAX: f64 = 78000.0;
+// Length and height are fixed at LENGTH and HEIGHT unless the run gives them bounds.
+fn get_length_bounds() -> (f64, f64) {
+    return config::get().length_bounds.unwrap_or((LENGTH, LENGTH));
+}
 
-//Scoring design parameters.
-fn score(
-    length: f64,
-    height: f64,
-    width: f64,
-    weight: f64,
-    cost: f64,
-    factor_of_safety: f64,
-    deflection: f64,
-    flight_hours: f64,
-) -> i64 {
-    let lengthcontrib = match length == LENGTH {
-        true => 0 as i64,
-        false => -i64::MAX / 10,
-    };
-    let heightcontrib = match height == HEIGHT {
-        true => 0 as i64,
-        false => -i64::MAX / 10,
-    };
-    let widthcontrib = match width < WIDTH_MAX && width > WIDTH_MIN {
-        true => 0 as i64,
-        false => -i64::MAX / 10,
-    };
-    let weightcontrib = match weight < WEIGHT_MAX {
-        true => -weight as i64,
-        false => -i64::MAX / 10,
-    };
-    let costcontrib = match cost < PRICE_MAX {
-        true => (PRICE_MAX / 3.0 - cost / 3.0) as i64,
-        false => -i64::MAX / 10,
-    };
-    let factorcontrib = match factor_of_safety < FOS_MAX && factor_of_safety > FOS_MIN {
-        true => factor_of_safety*WEIGHT_MAX as i64,
-        false => -i64::MAX / 10,
-    };
-    let deflectioncontrib = match deflection < DEFLECTION_MAX && deflection > -DEFLECTION_MAX {
-        true => 0 as i64,
-        false => -i64::MAX / 10,
-    };
-    let flight_hours_contrib =
-        match flight_hours > FATIGUE_FLIGHT_HOURS_MIN && flight_hours < FATIGUE_FLIGHT_HOURS_MAX {
-            true => 0 as i64,
-            false => -i64::MAX / 10,
-        };
+fn get_height_bounds() -> (f64, f64) {
+    return config::get().height_bounds.unwrap_or((HEIGHT, HEIGHT));
+}
 
-    return lengthcontrib
-        + heightcontrib
-        + widthcontrib
-        + weightcontrib
-        + costcontrib
-        + factorcontrib
-        + deflectioncontrib
-        + flight_hours_contrib;
-}
-
-fn get_rbeam_gscore(beam: beams::RectBeam) -> i64 {
-    return match beam.Thickness >= 0.1 * beam.Width
-        && beam.Thickness >= 0.1 * beam.Height
-        && beam.Thickness <= 0.5 * beam.Height
-        && beam.Thickness <= 0.5 * beam.Width
-    {
-        true => 0,
-        false => -i64::MAX / 10,
-    };
+fn random_dimension(rng: &mut impl Rng, (min, max): (f64, f64)) -> f64 {
+    return rng.gen::<f64>() * (max - min) + min;
 }
 
-fn get_tbeam_gscore(beam: TBeam) -> i64 {
-    return match beam.StemThickness >= 0.1 * beam.Width
-        && beam.FlangeThickness >= 0.1 * beam.Height
-        && beam.StemThickness <= 0.5 * beam.Height
-        && beam.FlangeThickness <= 0.5 * beam.Width
-    {
-        true => 0,
-        false => -i64::MAX / 10,
-    };
+// Bounds on length, height, width, then each plate thickness. Thicknesses are capped at half the largest
+// section dimension.
+fn get_gene_bounds(thicknesses: usize) -> Vec<(f64, f64)> {
+    let (length_min, length_max) = get_length_bounds();
+    let (height_min, height_max) = get_height_bounds();
+    let thickness_max = f64::max(WIDTH_MAX, height_max) / 2.0;
+    let mut bounds = vec![
+        (length_min, length_max),
+        (height_min, height_max),
+        (WIDTH_MIN, WIDTH_MAX),
+    ];
+    bounds.extend((0..thicknesses).map(|_| (THICKNESS_MIN, thickness_max)));
+    return bounds;
 }
 
-fn get_ibeam_gscore(beam: IBeam) -> i64 {
-    return match beam.CenterThickness >= 0.1 * beam.Width
-        && beam.FlangeThickness >= 0.1 * beam.Height
-        && beam.CenterThickness <= beam.Width
-        && beam.FlangeThickness <= 0.5 * beam.Height
-    {
-        true => 0,
-        false => -i64::MAX / 10,
-    };
+// Uniform mutation amplitudes per gene: half of each dimension's range, a tenth of that for thicknesses, over the
+// divisor of the beam type.
+fn get_mutation_steps(thicknesses: usize, divisor: f64) -> Vec<f64> {
+    let (length_min, length_max) = get_length_bounds();
+    let (height_min, height_max) = get_height_bounds();
+    let width_amp = (WIDTH_MAX - WIDTH_MIN) / 2.0;
+    let thickness_amp = width_amp * 0.1;
+    let mut steps = vec![
+        (length_max - length_min) / 2.0 / divisor,
+        (height_max - height_min) / 2.0 / divisor,
+        width_amp / divisor,
+    ];
+    steps.extend((0..thicknesses).map(|_| thickness_amp / divisor));
+    return steps;
 }
 
-fn get_rbeam_score(beam: RectBeam) -> i64 {
-    let height = beam.Height;
-    let width = beam.Width;
-    let length = beam.Length;
+// Under the 1/5th rule a mutation succeeds when it improves on the unmutated child. The mutated child's evaluation is
+// cached for selection, so only the unmutated child costs an extra one.
+fn record_mutation<T: Phenotype<BeamFitness>>(before: &T, after: &T) {
+    if config::get().mutation.one_fifth {
+        mutation::record(after.fitness() > before.fitness());
+    }
+}
 
+//Scoring design parameters against the configured objective and requirements, or the --score expression in their
+// place. Strength stops helping at the top of the factor of safety window, so no design can score its way out of
+// violating its requirements.
+fn score(
+    beam: &(impl Stress + Weight + Cost + DamageTolerance),
+    length: f64,
+    height: f64,
+    width: f64,
+) -> Evaluation {
+    let (length_min, length_max) = get_length_bounds();
+    let (height_min, height_max) = get_height_bounds();
     let weight = beam.weight();
     let cost = beam.cost();
+    let factor_of_safety = get_factor_of_safety(beam);
+    let deflection = get_deflection(beam);
+    let flight_hours = get_flight_hours(beam);
+
+    // Leaving the design space is a violation whatever the scoring.
+    let design_violation = constraints::outside(length, length_min, length_max)
+        + constraints::outside(height, height_min, height_max)
+        + constraints::outside(width, WIDTH_MIN, WIDTH_MAX);
 
-    let factor_of_safety = beam.factor_of_safety();
-    let deflection = beam.vertical_deflection();
-    let flight_hours = beam.flight_hours();
+    if let Some(spec) = &config::get().score {
+        let (tension, compression) = get_peak_stresses(beam);
+        let [ply_tension, ply_compression] = get_ply_margins(beam);
+        let mut values = vec![
+            length,
+            height,
+            width,
+            weight,
+            cost,
+            factor_of_safety,
+            deflection,
+            flight_hours,
+            tension,
+            compression,
+            ply_tension,
+            ply_compression,
+        ];
+        values.extend(get_expression_outputs(beam));
+        let evaluation = spec.evaluate(&values);
+        return Evaluation {
+            objective: evaluation.objective,
+            violation: evaluation.violation + design_violation,
+        };
+    }
 
-    return score(
-        length,
-        height,
-        width,
-        weight,
-        cost,
-        factor_of_safety,
-        deflection,
-        flight_hours,
-    ) + get_rbeam_gscore(beam);
+    let margin = factor_of_safety.clamp(0.0, FOS_MAX);
+    let violation = constraints::above(weight, WEIGHT_MAX)
+        + constraints::above(cost, PRICE_MAX)
+        + constraints::outside(factor_of_safety, FOS_MIN, FOS_MAX)
+        + constraints::above(deflection.abs(), DEFLECTION_MAX)
+        + constraints::outside(
+            flight_hours,
+            FATIGUE_FLIGHT_HOURS_MIN,
+            FATIGUE_FLIGHT_HOURS_MAX,
+        );
+
+    return Evaluation {
+        objective: config::get().objective.value(weight, cost, margin),
+        violation: violation + design_violation,
+    };
 }
 
-fn get_tbeam_score(beam: TBeam) -> i64 {
-    let height = beam.Height;
-    let width = beam.Width;
-    let length = beam.Length;
+// Scoring expression values after expression::VARIABLES: the factor of safety and deflection in each load case, then
+// the crack growth results with --damage-tolerance, NaN where crack growth does not apply.
+fn get_expression_outputs(beam: &(impl Stress + DamageTolerance)) -> Vec<f64> {
+    let run_config = config::get();
+    let mut values = Vec::new();
+    for case in &run_config.load_cases {
+        values.push(beam.factor_of_safety(case.temperature, run_config.ply_failure));
+        values.push(beam.vertical_deflection(case.temperature));
+    }
+    if let Some(crack) = run_config.damage_tolerance {
+        match beam.crack_growth(
+            crack,
+            run_config.detectable_crack,
+            run_config.load_spectrum.as_ref(),
+        ) {
+            Ok(growth) => values.extend([
+                growth.critical_length,
+                growth.hours_to_critical,
+                growth.inspection_interval_hours,
+            ]),
+            Err(_) => values.extend([f64::NAN; 3]),
+        }
+    }
+    return values;
+}
 
-    let weight = beam.weight();
-    let cost = beam.cost();
+// Proportions that keep each plate buildable.
+fn get_rbeam_gscore(beam: beams::RectBeam) -> f64 {
+    return constraints::below(beam.Thickness, 0.1 * beam.Width)
+        + constraints::below(beam.Thickness, 0.1 * beam.Height)
+        + constraints::above(beam.Thickness, 0.5 * beam.Height)
+        + constraints::above(beam.Thickness, 0.5 * beam.Width)
+        + constraints::below(beam.Thickness, THICKNESS_MIN);
+}
 
-    let factor_of_safety = beam.factor_of_safety();
-    let deflection = beam.vertical_deflection();
-    let flight_hours = beam.flight_hours();
+fn get_tbeam_gscore(beam: TBeam) -> f64 {
+    return constraints::below(beam.StemThickness, 0.1 * beam.Width)
+        + constraints::below(beam.FlangeThickness, 0.1 * beam.Height)
+        + constraints::above(beam.StemThickness, 0.5 * beam.Height)
+        + constraints::above(beam.FlangeThickness, 0.5 * beam.Width)
+        + constraints::below(beam.StemThickness, THICKNESS_MIN)
+        + constraints::below(beam.FlangeThickness, THICKNESS_MIN);
+}
 
-    return score(
-        length,
-        height,
-        width,
-        weight,
-        cost,
-        factor_of_safety,
-        deflection,
-        flight_hours,
-    ) + get_tbeam_gscore(beam);
+fn get_ibeam_gscore(beam: IBeam) -> f64 {
+    return constraints::below(beam.CenterThickness, 0.1 * beam.Width)
+        + constraints::below(beam.FlangeThickness, 0.1 * beam.Height)
+        + constraints::above(beam.CenterThickness, beam.Width)
+        + constraints::above(beam.FlangeThickness, 0.5 * beam.Height)
+        + constraints::below(beam.CenterThickness, THICKNESS_MIN)
+        + constraints::below(beam.FlangeThickness, THICKNESS_MIN);
 }
 
-fn get_ibeam_score(beam: IBeam) -> i64 {
-    let height = beam.Height;
-    let width = beam.Width;
-    let length = beam.Length;
+#[duplicate(
+    beam_type gscore;
+    [RectBeam] [get_rbeam_gscore];
+    [TBeam] [get_tbeam_gscore];
+    [IBeam] [get_ibeam_gscore]
+)]
+impl Evaluate for beam_type {
+    fn evaluation(&self) -> Evaluation {
+        let beam = *self;
+        return constraints::cached(beam.record(), || {
+            let evaluation = score(&beam, beam.Length, beam.Height, beam.Width);
+            return Evaluation {
+                objective: evaluation.objective,
+                violation: evaluation.violation + gscore(beam),
+            };
+        });
+    }
+}
 
-    let weight = beam.weight();
-    let cost = beam.cost();
+fn get_rbeam_score(beam: RectBeam) -> BeamFitness {
+    return BeamFitness::new(config::get().constraint_handling.fitness(beam.evaluation()));
+}
 
-    let factor_of_safety = beam.factor_of_safety();
-    let deflection = beam.vertical_deflection();
-    let flight_hours = beam.flight_hours();
+fn get_tbeam_score(beam: TBeam) -> BeamFitness {
+    return BeamFitness::new(config::get().constraint_handling.fitness(beam.evaluation()));
+}
 
-    return score(
-        length,
-        height,
-        width,
-        weight,
-        cost,
-        factor_of_safety,
-        deflection,
-        flight_hours,
-    ) + get_ibeam_gscore(beam);
+fn get_ibeam_score(beam: IBeam) -> BeamFitness {
+    return BeamFitness::new(config::get().constraint_handling.fitness(beam.evaluation()));
 }
 
-//Handling 'Fitness' for each beam.
-#[derive(Eq, PartialEq, PartialOrd, Ord)]
-struct BeamFitness {
-    score: i64,
+// Chance per child that a material gene is replaced by a random material from the library.
+const MATERIAL_MUTATION_RATE: f64 = 0.05;
+
+// Flanges use the configured flange material, otherwise the same material as the webs.
+fn get_flange_material(material: Material) -> Material {
+    return config::get().flange_material.unwrap_or(material);
 }
 
-impl Fitness for BeamFitness {
-    fn zero() -> BeamFitness {
-        BeamFitness { score: 0 }
+// Categorical crossover of the material gene: the child takes either parent's material.
+fn cross_material(this: Material, other: Material) -> Material {
+    if !config::get().material_gene {
+        return this;
     }
+    return match rng::thread_rng().gen::<bool>() {
+        true => this,
+        false => other,
+    };
+}
 
-    fn abs_diff(&self, other: &BeamFitness) -> BeamFitness {
-        BeamFitness {
-            score: self.score - other.score,
-        }
+fn mutate_material(material: Material) -> Material {
+    let mut rng = rng::thread_rng();
+    if !config::get().material_gene || rng.gen::<f64>() >= MATERIAL_MUTATION_RATE {
+        return material;
     }
+    let library: Vec<Material> = Material::iter().collect();
+    return library[rng.gen_range(0..library.len())];
 }
-impl Phenotype<i64> for RectBeam {
-    fn fitness(&self) -> i64 {
+
+impl Phenotype<BeamFitness> for RectBeam {
+    fn fitness(&self) -> BeamFitness {
         return get_rbeam_score(*self);
     }
 
     fn crossover(&self, other: &RectBeam) -> RectBeam {
-        return beams::RectBeam {
-            Material: self.Material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: (self.Width + other.Width) / 2.0,
-            Thickness: (self.Thickness + other.Thickness) / 2.0,
-        };
+        let material = cross_material(self.Material, other.Material);
+        let genes = config::get()
+            .rect_crossover
+            .cross(&self.genes(), &other.genes());
+        let mut child = self.with_genes(&genes);
+        child.Material = material;
+        child.FlangeMaterial = get_flange_material(material);
+        return child;
     }
 
     fn mutate(&self) -> RectBeam {
-        let mut rng = thread_rng();
-        let width_amp = (WIDTH_MAX - WIDTH_MIN) / 2.0;
-        let thickness_amp = width_amp * 0.1;
-        let new_width: f64 = self.Width + (rng.gen::<f64>() - 0.5) * width_amp / 10.0;
-        let new_thickness: f64 = self.Thickness + (rng.gen::<f64>() - 0.5) * thickness_amp / 10.0;
-        return beams::RectBeam {
-            Material: self.Material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: new_width,
-            Thickness: new_thickness,
-        };
+        let material = mutate_material(self.Material);
+        let genes = config::get().mutation.mutate(
+            &self.genes(),
+            &get_gene_bounds(1),
+            &get_mutation_steps(1, 10.0),
+        );
+        let mut child = self.with_genes(&genes);
+        child.Material = material;
+        child.FlangeMaterial = get_flange_material(material);
+        record_mutation(self, &child);
+        return child;
     }
 }
 
-impl Phenotype<i64> for TBeam {
-    fn fitness(&self) -> i64 {
+impl Phenotype<BeamFitness> for TBeam {
+    fn fitness(&self) -> BeamFitness {
         return get_tbeam_score(*self);
     }
 
     fn crossover(&self, other: &TBeam) -> TBeam {
-        return beams::TBeam {
-            Material: self.Material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: (self.Width + other.Width) / 2.0,
-            StemThickness: (self.StemThickness + other.StemThickness) / 2.0,
-            FlangeThickness: (self.FlangeThickness + other.FlangeThickness) / 2.0,
-        };
+        let material = cross_material(self.Material, other.Material);
+        let genes = config::get()
+            .t_crossover
+            .cross(&self.genes(), &other.genes());
+        let mut child = self.with_genes(&genes);
+        child.Material = material;
+        child.FlangeMaterial = get_flange_material(material);
+        return child;
     }
 
     fn mutate(&self) -> TBeam {
-        let mut rng = thread_rng();
-        let width_amp = (WIDTH_MAX - WIDTH_MIN) / 2.0;
-        let thickness_amp = width_amp * 0.1;
-        let new_width: f64 = self.Width + (rng.gen::<f64>() - 0.5) * width_amp / 20.0;
-        let new_stemthickness: f64 =
-            self.StemThickness + (rng.gen::<f64>() - 0.5) * thickness_amp / 20.0;
-        let new_flangethickness: f64 =
-            self.FlangeThickness + (rng.gen::<f64>() - 0.5) * thickness_amp / 20.0;
-        return beams::TBeam {
-            Material: self.Material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: new_width,
-            StemThickness: new_stemthickness,
-            FlangeThickness: new_flangethickness,
-        };
+        let material = mutate_material(self.Material);
+        let genes = config::get().mutation.mutate(
+            &self.genes(),
+            &get_gene_bounds(2),
+            &get_mutation_steps(2, 20.0),
+        );
+        let mut child = self.with_genes(&genes);
+        child.Material = material;
+        child.FlangeMaterial = get_flange_material(material);
+        record_mutation(self, &child);
+        return child;
     }
 }
 
-impl Phenotype<i64> for IBeam {
-    fn fitness(&self) -> i64 {
+impl Phenotype<BeamFitness> for IBeam {
+    fn fitness(&self) -> BeamFitness {
         return get_ibeam_score(*self);
     }
 
     fn crossover(&self, other: &IBeam) -> IBeam {
-        return beams::IBeam {
-            Material: self.Material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: (self.Width + other.Width) / 2.0,
-            CenterThickness: (self.CenterThickness + other.CenterThickness) / 2.0,
-            FlangeThickness: (self.FlangeThickness + other.FlangeThickness) / 2.0,
-        };
+        let material = cross_material(self.Material, other.Material);
+        let genes = config::get()
+            .i_crossover
+            .cross(&self.genes(), &other.genes());
+        let mut child = self.with_genes(&genes);
+        child.Material = material;
+        child.FlangeMaterial = get_flange_material(material);
+        return child;
     }
 
     fn mutate(&self) -> IBeam {
-        let mut rng = thread_rng();
-        let width_amp = (WIDTH_MAX - WIDTH_MIN) / 2.0;
-        let thickness_amp = width_amp * 0.1;
-        let new_width: f64 = self.Width + (rng.gen::<f64>() - 0.5) * width_amp / 20.0;
-        let new_stemthickness: f64 =
-            self.CenterThickness + (rng.gen::<f64>() - 0.5) * thickness_amp / 20.0;
-        let new_flangethickness: f64 =
-            self.FlangeThickness + (rng.gen::<f64>() - 0.5) * thickness_amp / 20.0;
-        return beams::IBeam {
-            Material: self.Material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: new_width,
-            CenterThickness: new_stemthickness,
-            FlangeThickness: new_flangethickness,
-        };
+        let material = mutate_material(self.Material);
+        let genes = config::get().mutation.mutate(
+            &self.genes(),
+            &get_gene_bounds(2),
+            &get_mutation_steps(2, 20.0),
+        );
+        let mut child = self.with_genes(&genes);
+        child.Material = material;
+        child.FlangeMaterial = get_flange_material(material);
+        record_mutation(self, &child);
+        return child;
     }
 }
 
@@ -327,164 +401,938 @@ fn get_material_string(material: Material) -> String {
         Material::Aluminum2024T4 => "Aluminum 2024-T4",
         Material::Aluminum6061T6 => "Aluminum 6061-T6",
         Material::TitaniumAlloyTi6AL4V => "Titanum Alloy Ti-6Al-4V",
+        Material::CarbonEpoxyQuasiIsotropic => "Carbon/Epoxy [0/45/-45/90]s",
+        Material::CarbonEpoxySparCap => "Carbon/Epoxy [0/0/45/-45/0/90]s",
+        Material::GlassEpoxyQuasiIsotropic => "E-Glass/Epoxy [0/45/-45/90]s",
     };
 
     return String::from(name);
 }
 
-fn get_rbeam_pop(size: u32, material: beams::Material) -> Vec<RectBeam> {
-    let mut rng = thread_rng();
+fn get_rbeam_pop(size: u32, materials: &[beams::Material]) -> Vec<RectBeam> {
+    let mut rng = rng::thread_rng();
     return (0..size)
-        .map(|i| beams::RectBeam {
-            Material: material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN,
-            Thickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN) / 10.0,
+        .map(|_| {
+            let material = materials[rng.gen_range(0..materials.len())];
+            let height = random_dimension(&mut rng, get_height_bounds());
+            beams::RectBeam {
+                Material: material,
+                FlangeMaterial: get_flange_material(material),
+                Length: random_dimension(&mut rng, get_length_bounds()),
+                Height: height,
+                Width: rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN,
+                Thickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN) / 10.0,
+            }
         })
         .collect();
 }
 
-fn get_tbeam_pop(size: u32, material: beams::Material) -> Vec<TBeam> {
-    let mut rng = thread_rng();
+fn get_tbeam_pop(size: u32, materials: &[beams::Material]) -> Vec<TBeam> {
+    let mut rng = rng::thread_rng();
     return (0..size)
-        .map(|i| beams::TBeam {
-            Material: material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN,
-            StemThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN) / 10.0,
-            FlangeThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + HEIGHT) / 10.0,
+        .map(|_| {
+            let material = materials[rng.gen_range(0..materials.len())];
+            let height = random_dimension(&mut rng, get_height_bounds());
+            beams::TBeam {
+                Material: material,
+                FlangeMaterial: get_flange_material(material),
+                Length: random_dimension(&mut rng, get_length_bounds()),
+                Height: height,
+                Width: rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN,
+                StemThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN) / 10.0,
+                FlangeThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + height) / 10.0,
+            }
         })
         .collect();
 }
 
-fn get_ibeam_pop(size: u32, material: beams::Material) -> Vec<IBeam> {
-    let mut rng = thread_rng();
+fn get_ibeam_pop(size: u32, materials: &[beams::Material]) -> Vec<IBeam> {
+    let mut rng = rng::thread_rng();
     return (0..size)
-        .map(|i| beams::IBeam {
-            Material: material,
-            Length: LENGTH,
-            Height: HEIGHT,
-            Width: rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN,
-            CenterThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN) / 10.0,
-            FlangeThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + HEIGHT) / 10.0,
+        .map(|_| {
+            let material = materials[rng.gen_range(0..materials.len())];
+            let height = random_dimension(&mut rng, get_height_bounds());
+            beams::IBeam {
+                Material: material,
+                FlangeMaterial: get_flange_material(material),
+                Length: random_dimension(&mut rng, get_length_bounds()),
+                Height: height,
+                Width: rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN,
+                CenterThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + WIDTH_MIN) / 10.0,
+                FlangeThickness: (rng.gen::<f64>() * (WIDTH_MAX - WIDTH_MIN) + height) / 10.0,
+            }
         })
         .collect();
 }
 
-fn output_beam_specs(beam: impl Stress + Cost + Weight) {
-    println!("Specs \n Cost: {} (<500000) \n Weight: {} (<78000) \n Flight Hours: {} (42000<->500000)\n Deflection: {} (-70<->70) \n FOS: {} (1.4<->2.2)", beam.cost(),beam.weight(),beam.flight_hours(),beam.vertical_deflection(),beam.factor_of_safety());
+fn output_beam_specs(
+    beam: impl Stress + Cost + Weight + DamageTolerance + Evaluate,
+    materials: &[Material],
+) {
+    println!("Specs \n Cost: {} (<500000) \n Weight: {} (<78000) \n Flight Hours: {} (42000<->500000)\n Deflection: {} (-70<->70) \n FOS: {} (1.4<->2.2)", beam.cost(),beam.weight(),get_flight_hours(&beam),get_deflection(&beam),get_factor_of_safety(&beam));
+    let evaluation = beam.evaluation();
+    if !evaluation.is_feasible() {
+        println!(
+            " INFEASIBLE: misses its requirements, violation {}",
+            evaluation.violation
+        );
+    }
+    let (tension, compression) = get_peak_stresses(&beam);
+    println!(
+        " Peak Stress: tension {} psi, compression {} psi",
+        tension, compression
+    );
+    for case in &config::get().load_cases {
+        println!(
+            " Load Case {} ({} F): FOS {}, Deflection {}",
+            case.name,
+            case.temperature,
+            beam.factor_of_safety(case.temperature, config::get().ply_failure),
+            beam.vertical_deflection(case.temperature)
+        );
+    }
+    if materials.iter().any(|material| is_laminate(*material)) {
+        println!(" Ply Failure: {}", config::get().ply_failure.name());
+    }
+    // A sweep evaluates a fixed grid, none of the search settings had a say in its result.
+    if config::get().sweep.is_none() {
+        println!(" Seed: {}", get_seed());
+        println!(
+            " Crossover: rect {}, T {}, I {}",
+            config::get().rect_crossover.name(),
+            config::get().t_crossover.name(),
+            config::get().i_crossover.name()
+        );
+        println!(" Optimizer: {}", config::get().optimizer.name());
+        println!(" Mutation: {}", config::get().mutation.name());
+        println!(
+            " Selection: {}, elitism {}",
+            config::get().selection.name(),
+            config::get().elitism
+        );
+    }
+    match &config::get().score {
+        Some(spec) => println!(" Scoring: {}", spec.text),
+        None => println!(" Objective: {}", config::get().objective.name()),
+    }
+    println!(
+        " Constraint Handling: {}, violation {}",
+        config::get().constraint_handling.name(),
+        evaluation.violation
+    );
+    println!(
+        " Mean Stress Correction: {}",
+        config::get().mean_stress_correction.name()
+    );
+    if let Some(spectrum) = &config::get().load_spectrum {
+        println!(
+            " Load History: {} ({} rainflow cycles over {} h)",
+            spectrum.source,
+            spectrum.cycles.len(),
+            spectrum.duration_hours
+        );
+    }
+    if let Some(crack) = config::get().damage_tolerance {
+        output_crack_growth(&beam, crack);
+    }
+}
+
+fn output_crack_growth(beam: &impl DamageTolerance, crack: config::CrackType) {
+    let run_config = config::get();
+    let growth = match beam.crack_growth(
+        crack,
+        run_config.detectable_crack,
+        run_config.load_spectrum.as_ref(),
+    ) {
+        Ok(growth) => growth,
+        Err(message) => {
+            println!("Damage Tolerance: {}", message);
+            return;
+        }
+    };
+    println!(
+        "Damage Tolerance ({} crack in flange)\n Detectable Crack: {} in \n Critical Crack: {} in \n Flight Hours to Critical: {} \n Inspection Interval: {} flight hours",
+        crack.name(),
+        growth.detectable_length,
+        growth.critical_length,
+        growth.hours_to_critical,
+        growth.inspection_interval_hours
+    );
+    if run_config.load_spectrum.is_some() {
+        println!(" Flights to Critical: {}", growth.blocks_to_critical);
+    }
+    if growth.truncated {
+        println!(
+            " Crack growth stopped short of the critical crack, the life above is a lower bound"
+        );
+    }
 }
 
 fn output_rbeam(beam: RectBeam) {
     println!(
-        "Rectangular Beam\n Length: {} \n Height: {} \n Width: {} \n Thickness: {} \n Material: {}\n Score: {}", beam.Length, beam.Height, beam.Width, beam.Thickness, get_material_string(beam.Material),get_rbeam_score(beam)
+        "Rectangular Beam\n Length: {} \n Height: {} \n Width: {} \n Thickness: {} \n Material: {} \n FlangeMaterial: {}\n Score: {}", beam.Length, beam.Height, beam.Width, beam.Thickness, get_material_string(beam.Material), get_material_string(beam.FlangeMaterial),get_rbeam_score(beam)
     );
-    output_beam_specs(beam);
+    output_beam_specs(beam, &[beam.Material, beam.FlangeMaterial]);
 }
 
 fn output_tbeam(beam: TBeam) {
     println!(
-        "T Beam\n Length: {} \n Height: {} \n Width: {} \n StemThickness: {} \n FlangeThickness: {} \n Material: {}\n Score: {}", beam.Length, beam.Height, beam.Width, beam.StemThickness,beam.FlangeThickness, get_material_string(beam.Material),get_tbeam_score(beam)
+        "T Beam\n Length: {} \n Height: {} \n Width: {} \n StemThickness: {} \n FlangeThickness: {} \n Material: {} \n FlangeMaterial: {}\n Score: {}", beam.Length, beam.Height, beam.Width, beam.StemThickness,beam.FlangeThickness, get_material_string(beam.Material), get_material_string(beam.FlangeMaterial),get_tbeam_score(beam)
     );
-    output_beam_specs(beam);
+    output_beam_specs(beam, &[beam.Material, beam.FlangeMaterial]);
 }
 
 fn output_ibeam(beam: IBeam) {
     println!(
-        "I Beam\n Length: {} \n Height: {} \n Width: {} \n CenterThickness: {} \n FlangeThickness: {} \n Material: {}\n Score: {}", beam.Length, beam.Height, beam.Width, beam.CenterThickness,beam.FlangeThickness, get_material_string(beam.Material),get_ibeam_score(beam)
+        "I Beam\n Length: {} \n Height: {} \n Width: {} \n CenterThickness: {} \n FlangeThickness: {} \n Material: {} \n FlangeMaterial: {}\n Score: {}", beam.Length, beam.Height, beam.Width, beam.CenterThickness,beam.FlangeThickness, get_material_string(beam.Material), get_material_string(beam.FlangeMaterial),get_ibeam_score(beam)
     );
 
-    output_beam_specs(beam);
+    output_beam_specs(beam, &[beam.Material, beam.FlangeMaterial]);
+}
+
+fn output_beam(beam: Beams) {
+    match beam {
+        Beams::R(a) => output_rbeam(a),
+        Beams::T(a) => output_tbeam(a),
+        Beams::I(a) => output_ibeam(a),
+    }
+}
+
+impl Evaluate for Beams {
+    fn evaluation(&self) -> Evaluation {
+        return match self {
+            Beams::R(a) => a.evaluation(),
+            Beams::T(a) => a.evaluation(),
+            Beams::I(a) => a.evaluation(),
+        };
+    }
+}
+
+fn get_beam_score(beam: Beams) -> BeamFitness {
+    return match beam {
+        Beams::R(a) => get_rbeam_score(a),
+        Beams::T(a) => get_tbeam_score(a),
+        Beams::I(a) => get_ibeam_score(a),
+    };
 }
 
-#[derive(Copy, Clone)]
-enum Beams {
-    R(RectBeam),
-    T(TBeam),
-    I(IBeam),
+// Chance per child that the shape gene switches to another section type.
+const SHAPE_MUTATION_RATE: f64 = 0.02;
+
+// Shape conversions keep the shared dimensions and map the plate thicknesses onto the closest equivalent:
+// the box wall becomes both the web and the flange thickness and vice versa.
+impl Beams {
+    fn as_rect(&self) -> RectBeam {
+        return match *self {
+            Beams::R(a) => a,
+            Beams::T(a) => RectBeam {
+                Material: a.Material,
+                FlangeMaterial: a.FlangeMaterial,
+                Length: a.Length,
+                Width: a.Width,
+                Height: a.Height,
+                Thickness: (a.StemThickness + a.FlangeThickness) / 2.0,
+            },
+            Beams::I(a) => RectBeam {
+                Material: a.Material,
+                FlangeMaterial: a.FlangeMaterial,
+                Length: a.Length,
+                Width: a.Width,
+                Height: a.Height,
+                Thickness: (a.CenterThickness + a.FlangeThickness) / 2.0,
+            },
+        };
+    }
+
+    fn as_t(&self) -> TBeam {
+        return match *self {
+            Beams::T(a) => a,
+            Beams::R(a) => TBeam {
+                Material: a.Material,
+                FlangeMaterial: a.FlangeMaterial,
+                Length: a.Length,
+                Width: a.Width,
+                Height: a.Height,
+                StemThickness: a.Thickness,
+                FlangeThickness: a.Thickness,
+            },
+            Beams::I(a) => TBeam {
+                Material: a.Material,
+                FlangeMaterial: a.FlangeMaterial,
+                Length: a.Length,
+                Width: a.Width,
+                Height: a.Height,
+                StemThickness: a.CenterThickness,
+                FlangeThickness: a.FlangeThickness,
+            },
+        };
+    }
+
+    fn as_i(&self) -> IBeam {
+        return match *self {
+            Beams::I(a) => a,
+            Beams::R(a) => IBeam {
+                Material: a.Material,
+                FlangeMaterial: a.FlangeMaterial,
+                Length: a.Length,
+                Width: a.Width,
+                Height: a.Height,
+                CenterThickness: a.Thickness,
+                FlangeThickness: a.Thickness,
+            },
+            Beams::T(a) => IBeam {
+                Material: a.Material,
+                FlangeMaterial: a.FlangeMaterial,
+                Length: a.Length,
+                Width: a.Width,
+                Height: a.Height,
+                CenterThickness: a.StemThickness,
+                FlangeThickness: a.FlangeThickness,
+            },
+        };
+    }
+
+    // Same beam re-expressed as the shape of `shape`.
+    fn with_shape_of(&self, shape: &Beams) -> Beams {
+        return match shape {
+            Beams::R(_) => Beams::R(self.as_rect()),
+            Beams::T(_) => Beams::T(self.as_t()),
+            Beams::I(_) => Beams::I(self.as_i()),
+        };
+    }
+}
+
+impl Phenotype<BeamFitness> for Beams {
+    fn fitness(&self) -> BeamFitness {
+        return get_beam_score(*self);
+    }
+
+    // Parents of different shapes breed in the shape of a randomly chosen parent.
+    fn crossover(&self, other: &Beams) -> Beams {
+        let (first, second) = match rng::thread_rng().gen::<bool>() {
+            true => (*self, *other),
+            false => (*other, *self),
+        };
+        return match (first, second.with_shape_of(&first)) {
+            (Beams::R(a), Beams::R(b)) => Beams::R(a.crossover(&b)),
+            (Beams::T(a), Beams::T(b)) => Beams::T(a.crossover(&b)),
+            (Beams::I(a), Beams::I(b)) => Beams::I(a.crossover(&b)),
+            _ => unreachable!(),
+        };
+    }
+
+    fn mutate(&self) -> Beams {
+        let mut rng = rng::thread_rng();
+        let mut beam = *self;
+        if rng.gen::<f64>() < SHAPE_MUTATION_RATE {
+            beam = match (beam, rng.gen::<bool>()) {
+                (Beams::R(_), true) | (Beams::I(_), false) => Beams::T(beam.as_t()),
+                (Beams::T(_), true) | (Beams::R(_), false) => Beams::I(beam.as_i()),
+                (Beams::I(_), true) | (Beams::T(_), false) => Beams::R(beam.as_rect()),
+            };
+        }
+        return match beam {
+            Beams::R(a) => Beams::R(a.mutate()),
+            Beams::T(a) => Beams::T(a.mutate()),
+            Beams::I(a) => Beams::I(a.mutate()),
+        };
+    }
+}
+
+impl Genes for RectBeam {
+    fn genes(&self) -> Vec<f64> {
+        return vec![self.Length, self.Height, self.Width, self.Thickness];
+    }
+
+    fn with_genes(&self, genes: &[f64]) -> RectBeam {
+        return RectBeam {
+            Length: genes[0],
+            Height: genes[1],
+            Width: genes[2],
+            Thickness: genes[3],
+            ..*self
+        };
+    }
+
+    fn gene_names(&self) -> Vec<&'static str> {
+        return vec!["length", "height", "width", "thickness"];
+    }
+}
+
+impl Genes for TBeam {
+    fn genes(&self) -> Vec<f64> {
+        return vec![
+            self.Length,
+            self.Height,
+            self.Width,
+            self.StemThickness,
+            self.FlangeThickness,
+        ];
+    }
+
+    fn with_genes(&self, genes: &[f64]) -> TBeam {
+        return TBeam {
+            Length: genes[0],
+            Height: genes[1],
+            Width: genes[2],
+            StemThickness: genes[3],
+            FlangeThickness: genes[4],
+            ..*self
+        };
+    }
+
+    fn gene_names(&self) -> Vec<&'static str> {
+        return vec!["length", "height", "width", "stem", "flange"];
+    }
+}
+
+impl Genes for IBeam {
+    fn genes(&self) -> Vec<f64> {
+        return vec![
+            self.Length,
+            self.Height,
+            self.Width,
+            self.CenterThickness,
+            self.FlangeThickness,
+        ];
+    }
+
+    fn with_genes(&self, genes: &[f64]) -> IBeam {
+        return IBeam {
+            Length: genes[0],
+            Height: genes[1],
+            Width: genes[2],
+            CenterThickness: genes[3],
+            FlangeThickness: genes[4],
+            ..*self
+        };
+    }
+
+    fn gene_names(&self) -> Vec<&'static str> {
+        return vec!["length", "height", "width", "center", "flange"];
+    }
+}
+
+impl Genes for Beams {
+    fn genes(&self) -> Vec<f64> {
+        return match self {
+            Beams::R(a) => a.genes(),
+            Beams::T(a) => a.genes(),
+            Beams::I(a) => a.genes(),
+        };
+    }
+
+    fn with_genes(&self, genes: &[f64]) -> Beams {
+        return match self {
+            Beams::R(a) => Beams::R(a.with_genes(genes)),
+            Beams::T(a) => Beams::T(a.with_genes(genes)),
+            Beams::I(a) => Beams::I(a.with_genes(genes)),
+        };
+    }
+
+    fn gene_names(&self) -> Vec<&'static str> {
+        return match self {
+            Beams::R(a) => a.gene_names(),
+            Beams::T(a) => a.gene_names(),
+            Beams::I(a) => a.gene_names(),
+        };
+    }
+}
+
+// Mixed population with an equal share of each shape.
+fn get_beam_pop(size: u32, materials: &[beams::Material]) -> Vec<Beams> {
+    let share = size / 3;
+    let mut population: Vec<Beams> = Vec::new();
+    population.extend(get_rbeam_pop(share, materials).into_iter().map(Beams::R));
+    population.extend(get_tbeam_pop(share, materials).into_iter().map(Beams::T));
+    population.extend(
+        get_ibeam_pop(size - 2 * share, materials)
+            .into_iter()
+            .map(Beams::I),
+    );
+    return population;
+}
+
+// What a simulation thread sends back, tagged with its index: statistics every generation, then the winner
+// with the generation it stopped at and why.
+enum Message {
+    Progress(u64, GenerationStats),
+    Result(u64, Beams, u64, StopReason),
+}
+
+fn run_simulation<T>(
+    name: &str,
+    mut population: Vec<T>,
+    index: u64,
+    started: Instant,
+    transmitter: &Sender<Message>,
+) -> ga::Outcome<T>
+where
+    T: Phenotype<BeamFitness> + Genes + Evaluate + Record + Copy + Send + Sync,
+{
+    let seed = rng::derive_seed(get_seed(), index);
+    let selector = config::get().selection.selector::<T>(POP_SURVIVORS);
+    let mut stopper = EarlyStopper::new(config::get().stopping.clone(), started);
+    let mut first_generation = 0;
+    let mut history: Vec<GenerationStats> = Vec::new();
+
+    let checkpoint_dir = config::get().checkpoint_dir.as_deref();
+    if let (Some(dir), true) = (checkpoint_dir, config::get().resume) {
+        match checkpoint::resume::<T>(dir, name, &mut stopper) {
+            Ok(Some(saved)) => {
+                eprintln!(
+                    "Resuming {} from generation {}, best score {}",
+                    name,
+                    saved.generation,
+                    saved.best.fitness()
+                );
+                population = saved.population;
+                first_generation = saved.generation;
+                for &stats in &saved.history {
+                    transmitter
+                        .send(Message::Progress(index, stats))
+                        .expect("Fail to send progress.");
+                }
+                history = saved.history;
+            }
+            Ok(None) => {}
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut report = |generation: u64, population: &[T]| {
+        let stats = GenerationStats::from_population(generation, population);
+        transmitter
+            .send(Message::Progress(index, stats))
+            .expect("Fail to send progress.");
+        let stop = stopper.update(&stats);
+        constraints::set_generation(generation);
+        if checkpoint_dir.is_some() {
+            history.push(stats);
+        }
+        if generation % CHECKPOINT_GENERATIONS == 0 {
+            rng::seed(rng::derive_seed(seed, generation));
+            if let Some(dir) = checkpoint_dir {
+                let saved = Checkpoint::capture(generation, seed, &stopper, population, &history);
+                if let Err(message) = checkpoint::save(&checkpoint::path(dir, name), &saved) {
+                    eprintln!("{}", message);
+                }
+            }
+        }
+        return stop;
+    };
+    return config::get()
+        .optimizer
+        .run(
+            &mut population,
+            Breeding {
+                selector: selector.as_ref(),
+                elites: config::get().elitism,
+            },
+            first_generation,
+            GENETIC_ITERS,
+            &|individual: &T| get_gene_bounds(individual.genes().len() - 3),
+            &mut report,
+        )
+        .unwrap();
+}
+
+// Grid search in place of the simulations, reporting the best feasible design like the winner of a run.
+fn run_sweep(dir: &str) {
+    let materials: Vec<Material> = Material::iter().collect();
+    let rects: Vec<(Material, RectBeam)> = materials
+        .iter()
+        .map(|&material| (material, get_rbeam_pop(1, &[material])[0]))
+        .collect();
+    let ts: Vec<(Material, TBeam)> = materials
+        .iter()
+        .map(|&material| (material, get_tbeam_pop(1, &[material])[0]))
+        .collect();
+    let is: Vec<(Material, IBeam)> = materials
+        .iter()
+        .map(|&material| (material, get_ibeam_pop(1, &[material])[0]))
+        .collect();
+    let bounds = |beam: &Beams| get_gene_bounds(beam.genes().len() - 3);
+    match sweep::run(dir, &rects, &ts, &is, &bounds) {
+        Ok(Some(beam)) => {
+            output_beam(beam);
+            write_best_beam(beam);
+        }
+        Ok(None) => println!("No feasible design on the grid"),
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Plots, drawings and meshes of the chosen beam, the ones the run asks for.
+fn write_best_beam(beam: Beams) {
+    let run_config = config::get();
+    let bounds = get_gene_bounds(beam.genes().len() - 3);
+    let written = [
+        run_config
+            .plot
+            .as_ref()
+            .map(|dir| plot::write_beam(dir, beam, &bounds)),
+        run_config
+            .drawing
+            .as_ref()
+            .map(|dir| drawing::write_beam(dir, beam)),
+        run_config
+            .mesh
+            .as_ref()
+            .map(|dir| mesh::write_beam(dir, beam)),
+    ];
+    for result in written.iter().flatten() {
+        if let Err(message) = result {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+// One line on stderr, rewritten in place, summarizing the slowest running simulation and the best score so far.
+fn output_progress(latest: &[Option<GenerationStats>], done: &[bool]) {
+    let finished = done.iter().filter(|&&d| d).count();
+    let generation = latest
+        .iter()
+        .zip(done)
+        .filter(|(_, &d)| !d)
+        .map(|(stats, _)| stats.map_or(0, |s| s.generation))
+        .min()
+        .unwrap_or(GENETIC_ITERS);
+    let best = latest
+        .iter()
+        .flatten()
+        .map(|s| BeamFitness::new(s.best))
+        .max();
+    let feasible: usize = latest.iter().flatten().map(|s| s.feasible).sum();
+    eprint!(
+        "\rGeneration {}/{}, {}/{} simulations done, best score {}, {} feasible   ",
+        generation,
+        GENETIC_ITERS,
+        finished,
+        latest.len(),
+        best.map_or(String::from("-"), |b| b.to_string()),
+        feasible
+    );
+}
+
+fn get_seed() -> u64 {
+    return config::get().seed.unwrap_or(0);
 }
 
 fn main() {
-    let (tx, rx): (Sender<Beams>, Receiver<Beams>) = mpsc::channel();
+    match config::from_args(std::env::args().skip(1)) {
+        Ok(mut run_config) => {
+            if let Err(message) = checkpoint::resolve_seed(&mut run_config) {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+            config::init(run_config)
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(dir) = &config::get().sweep {
+        run_sweep(dir);
+        return;
+    }
+
+    // Messages carry the index of their simulation, which also picks its seed, so ties resolve the same way every run.
+    let (tx, rx) = mpsc::channel::<Message>();
+    let mut names: Vec<String> = Vec::new();
+    let started = Instant::now();
+
+    // With the material gene one population per shape covers the whole library, otherwise each material gets its own.
+    let material_groups: Vec<Vec<Material>> = match config::get().material_gene {
+        true => vec![beams::Material::iter().collect()],
+        false => beams::Material::iter()
+            .map(|material| vec![material])
+            .collect(),
+    };
 
-    for material in beams::Material::iter() {
+    for materials in material_groups {
+        let group = match materials.len() {
+            1 => format!("{:?}", materials[0]),
+            _ => String::from("AllMaterials"),
+        };
+        if config::get().shape_gene {
+            let transmitter = tx.clone();
+            let index = names.len() as u64;
+            let name = format!("{}_mixed", group);
+            names.push(name.clone());
+            thread::spawn(move || {
+                rng::seed(rng::derive_seed(get_seed(), index));
+                let result = run_simulation(
+                    &name,
+                    get_beam_pop(POP_SIZE, &materials),
+                    index,
+                    started,
+                    &transmitter,
+                );
+                transmitter
+                    .send(Message::Result(
+                        index,
+                        result.best,
+                        result.generations,
+                        result.stop,
+                    ))
+                    .expect("Fail to send result.");
+                drop(transmitter);
+            });
+            continue;
+        }
         let transmitter = tx.clone();
+        let rbeam_materials = materials.clone();
+        let index = names.len() as u64;
+        let name = format!("{}_rect", group);
+        names.push(name.clone());
         thread::spawn(move || {
-            let mut rbeam_pop = get_rbeam_pop(POP_SIZE, material);
-            let mut sim = Simulator::builder(&mut rbeam_pop)
-                .set_selector(Box::new(UnstableMaximizeSelector::new(POP_SURVIVORS)))
-                .set_max_iters(GENETIC_ITERS)
-                .build();
-            sim.run();
-            let result = sim.get().unwrap();
+            rng::seed(rng::derive_seed(get_seed(), index));
+            let result = run_simulation(
+                &name,
+                get_rbeam_pop(POP_SIZE, &rbeam_materials),
+                index,
+                started,
+                &transmitter,
+            );
             transmitter
-                .send(Beams::R(*result))
+                .send(Message::Result(
+                    index,
+                    Beams::R(result.best),
+                    result.generations,
+                    result.stop,
+                ))
                 .expect("Fail to send result.");
             drop(transmitter);
         });
         let transmitter = tx.clone();
+        let tbeam_materials = materials.clone();
+        let index = names.len() as u64;
+        let name = format!("{}_t", group);
+        names.push(name.clone());
         thread::spawn(move || {
-            let mut tbeam_pop = get_tbeam_pop(POP_SIZE, material);
-            let mut sim = Simulator::builder(&mut tbeam_pop)
-                .set_selector(Box::new(UnstableMaximizeSelector::new(POP_SURVIVORS)))
-                .set_max_iters(GENETIC_ITERS)
-                .build();
-            sim.run();
-            let result = sim.get().unwrap();
+            rng::seed(rng::derive_seed(get_seed(), index));
+            let result = run_simulation(
+                &name,
+                get_tbeam_pop(POP_SIZE, &tbeam_materials),
+                index,
+                started,
+                &transmitter,
+            );
             transmitter
-                .send(Beams::T(*result))
+                .send(Message::Result(
+                    index,
+                    Beams::T(result.best),
+                    result.generations,
+                    result.stop,
+                ))
                 .expect("Fail to send result.");
             drop(transmitter);
         });
         let transmitter = tx.clone();
+        let ibeam_materials = materials.clone();
+        let index = names.len() as u64;
+        let name = format!("{}_i", group);
+        names.push(name.clone());
         thread::spawn(move || {
-            let mut ibeam_pop = get_ibeam_pop(POP_SIZE, material);
-            let mut sim = Simulator::builder(&mut ibeam_pop)
-                .set_selector(Box::new(UnstableMaximizeSelector::new(POP_SURVIVORS)))
-                .set_max_iters(GENETIC_ITERS)
-                .build();
-            sim.run();
-            let result = sim.get().unwrap();
+            rng::seed(rng::derive_seed(get_seed(), index));
+            let result = run_simulation(
+                &name,
+                get_ibeam_pop(POP_SIZE, &ibeam_materials),
+                index,
+                started,
+                &transmitter,
+            );
             transmitter
-                .send(Beams::I(*result))
+                .send(Message::Result(
+                    index,
+                    Beams::I(result.best),
+                    result.generations,
+                    result.stop,
+                ))
                 .expect("Fail to send result.");
             drop(transmitter);
         });
     }
 
     drop(tx);
-    let mut best_beams: Vec<Beams> = Vec::new();
+    let mut best_beams: Vec<(u64, Beams)> = Vec::new();
+    let mut stops: Vec<Option<(u64, StopReason, Duration)>> = vec![None; names.len()];
+    let mut done: Vec<bool> = vec![false; names.len()];
+    let mut latest: Vec<Option<GenerationStats>> = vec![None; names.len()];
+    let mut histories: Vec<Vec<GenerationStats>> = vec![Vec::new(); names.len()];
+    let mut last_output = Instant::now();
 
-    for beam in rx {
-        best_beams.push(beam);
+    for message in rx {
+        match message {
+            Message::Progress(index, stats) => {
+                latest[index as usize] = Some(stats);
+                if config::get().history_dir.is_some() {
+                    histories[index as usize].push(stats);
+                }
+            }
+            Message::Result(index, beam, generations, stop) => {
+                best_beams.push((index, beam));
+                stops[index as usize] = Some((generations, stop, started.elapsed()));
+                done[index as usize] = true;
+            }
+        }
+        if last_output.elapsed() > Duration::from_millis(500) {
+            output_progress(&latest, &done);
+            last_output = Instant::now();
+        }
+    }
+    output_progress(&latest, &done);
+    eprintln!();
+    best_beams.sort_by_key(|&(index, _)| index);
+
+    println!("Simulations");
+    for &(index, beam) in &best_beams {
+        if let Some((generations, stop, elapsed)) = stops[index as usize] {
+            println!(
+                " {}: score {}, stopped at generation {} ({}) after {:.1} s",
+                names[index as usize],
+                get_beam_score(beam),
+                generations,
+                stop.name(),
+                elapsed.as_secs_f64()
+            );
+        }
     }
 
-    let mut current_max_score = -i64::MAX;
+    if config::get().refine {
+        println!("Refinement");
+        for (index, beam) in best_beams.iter_mut() {
+            let refinement = refine::refine(beam, &get_gene_bounds(beam.genes().len() - 3));
+            println!(
+                " {}: score {} -> {}, objective {} -> {}, violation {} -> {} ({} evaluations)",
+                names[*index as usize],
+                get_beam_score(*beam),
+                get_beam_score(refinement.design),
+                refinement.before.objective,
+                refinement.after.objective,
+                refinement.before.violation,
+                refinement.after.violation,
+                refinement.evaluations
+            );
+            *beam = refinement.design;
+        }
+    }
+
+    if let Some(dir) = &config::get().history_dir {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("Cannot create history directory {}: {}", dir, e);
+        }
+        for (name, history) in names.iter().zip(&histories) {
+            let path = Path::new(dir).join(format!("{}.csv", name));
+            if let Err(message) = history::write_csv(&path, history) {
+                eprintln!("{}", message);
+            }
+        }
+    }
+
+    // Feasible designs rank ahead of infeasible ones whatever their scores.
+    let mut current_max_score = (false, BeamFitness::new(f64::NEG_INFINITY));
     let mut best_beam = Beams::R(beams::RectBeam {
         Material: beams::Material::Steel1018,
+        FlangeMaterial: beams::Material::Steel1018,
         Height: 10.0,
         Width: 10.0,
         Thickness: 10.0,
         Length: 10.0,
     });
 
-    for beam in best_beams {
-        let score = match beam {
-            Beams::R(a) => get_rbeam_score(a),
-            Beams::T(a) => get_tbeam_score(a),
-            Beams::I(a) => get_ibeam_score(a),
-        };
+    for (_, beam) in best_beams {
+        let score = (beam.evaluation().is_feasible(), get_beam_score(beam));
         if score > current_max_score {
             current_max_score = score;
             best_beam = beam;
         }
     }
 
-    match best_beam {
-        Beams::R(a) => output_rbeam(a),
-        Beams::T(a) => output_tbeam(a),
-        Beams::I(a) => output_ibeam(a),
+    output_beam(best_beam);
+    write_best_beam(best_beam);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsgenetic::sim::select::UnstableMaximizeSelector;
+
+    // Short seeded run of the rectangular beam optimization, returning the winner's genes and score.
+    fn seeded_rbeam_run(seed: u64) -> (f64, f64, f64, f64, f64) {
+        rng::seed(seed);
+        let mut population = get_rbeam_pop(100, &[Material::Steel1018]);
+        let selector = UnstableMaximizeSelector::new(20);
+        let beam = ga::evolve(&mut population, &selector, 0, 0, 50, &mut |_, _| None)
+            .unwrap()
+            .best;
+        return (
+            beam.Length,
+            beam.Height,
+            beam.Width,
+            beam.Thickness,
+            get_rbeam_score(beam).value(),
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_same_design() {
+        assert_eq!(seeded_rbeam_run(42), seeded_rbeam_run(42));
+    }
+
+    #[test]
+    fn different_seeds_give_different_designs() {
+        assert_ne!(seeded_rbeam_run(42), seeded_rbeam_run(43));
+    }
+
+    fn test_rbeam() -> RectBeam {
+        return RectBeam {
+            Material: Material::Aluminum6061T6,
+            FlangeMaterial: Material::Aluminum6061T6,
+            Length: LENGTH,
+            Height: HEIGHT,
+            Width: 30.0,
+            Thickness: 4.0,
+        };
+    }
+
+    #[test]
+    fn spectrum_damage_sums_over_cycles() {
+        let beam = test_rbeam();
+        let correction = fatigue::MeanStressCorrection::Goodman;
+        // The fixed amplitude assumption of flight_hours written as a spectrum: 27 cycles an hour up to 2.33g.
+        let spectrum = |count: f64| fatigue::LoadSpectrum {
+            source: String::from("test"),
+            cycles: vec![fatigue::Cycle {
+                range: 2.33,
+                mean: 2.33,
+                count,
+            }],
+            duration_hours: 1.0,
+        };
+        let hours = beam.flight_hours(correction);
+        let single = beam.spectrum_flight_hours(&spectrum(27.0), correction);
+        let double = beam.spectrum_flight_hours(&spectrum(54.0), correction);
+        assert!(hours.is_finite() && hours > 0.0);
+        assert!((single - hours).abs() < 1.0e-9 * hours);
+        assert!((double - hours / 2.0).abs() < 1.0e-9 * hours);
+    }
+
+    #[test]
+    fn simulation_seeds_are_distinct() {
+        let seeds: Vec<u64> = (0..12).map(|index| rng::derive_seed(7, index)).collect();
+        for (index, seed) in seeds.iter().enumerate() {
+            assert!(!seeds[index + 1..].contains(seed));
+            assert_eq!(*seed, rng::derive_seed(7, index as u64));
+        }
     }
 }
@@ -0,0 +1,207 @@
+use crate::beams::{Beams, Plate, Plates};
+use crate::drawing::{self, Region};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Triangulated closed surface, coordinates in inches.
+pub struct Mesh {
+    pub vertices: Vec<[f64; 3]>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+fn subtract(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    return [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    return [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ];
+}
+
+fn normal(mesh: &Mesh, triangle: [usize; 3]) -> [f64; 3] {
+    let [a, b, c] = triangle.map(|index| mesh.vertices[index]);
+    let n = cross(subtract(b, a), subtract(c, a));
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    return match length > 0.0 {
+        true => n.map(|component| component / length),
+        false => [0.0; 3],
+    };
+}
+
+impl Mesh {
+    // Two triangles over the quad a b c d, wound anticlockwise seen from the `outward` side.
+    fn quad(&mut self, corners: [usize; 4], outward: [f64; 3]) {
+        let [a, b, c, d] = corners;
+        let n = normal(self, [a, b, c]);
+        let facing = n[0] * outward[0] + n[1] * outward[1] + n[2] * outward[2];
+        match facing >= 0.0 {
+            true => self.triangles.extend([[a, b, c], [a, c, d]]),
+            false => self.triangles.extend([[a, c, b], [a, d, c]]),
+        }
+    }
+}
+
+// The section extruded along z from 0 to `length`, with x across the width and y up from the bottom of the section.
+// Beams have one section over their length, so the solid is a prism. The end caps are the grid cells of the
+// section and the walls its boundary edges, so every edge is shared by exactly two triangles.
+pub fn extrude(plates: &[Plate], length: f64) -> Mesh {
+    let Region {
+        xs,
+        ys,
+        cells,
+        boundary,
+    } = drawing::region(plates);
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        triangles: Vec::new(),
+    };
+    let bottom = ys.last().cloned().unwrap_or(0.0);
+    let left = xs.first().cloned().unwrap_or(0.0);
+    let mut indices: HashMap<(i64, i64, bool), usize> = HashMap::new();
+    let mut vertex = |mesh: &mut Mesh, (i, j): (i64, i64), far: bool| {
+        return *indices.entry((i, j, far)).or_insert_with(|| {
+            mesh.vertices.push([
+                xs[i as usize] - left,
+                bottom - ys[j as usize],
+                match far {
+                    true => length,
+                    false => 0.0,
+                },
+            ]);
+            mesh.vertices.len() - 1
+        });
+    };
+
+    for &(i, j) in &cells {
+        for (far, outward) in [(false, [0.0, 0.0, -1.0]), (true, [0.0, 0.0, 1.0])] {
+            let corners = [
+                vertex(&mut mesh, (i, j), far),
+                vertex(&mut mesh, (i + 1, j), far),
+                vertex(&mut mesh, (i + 1, j + 1), far),
+                vertex(&mut mesh, (i, j + 1), far),
+            ];
+            mesh.quad(corners, outward);
+        }
+    }
+    for &(from, to) in &boundary {
+        // The filled cell is on the right of the edge, so outward is the edge direction, with y flipped up, turned
+        // anticlockwise.
+        let direction = ((to.0 - from.0) as f64, -((to.1 - from.1) as f64));
+        let corners = [
+            vertex(&mut mesh, from, false),
+            vertex(&mut mesh, to, false),
+            vertex(&mut mesh, to, true),
+            vertex(&mut mesh, from, true),
+        ];
+        mesh.quad(corners, [-direction.1, direction.0, 0.0]);
+    }
+    return mesh;
+}
+
+pub fn write_stl(path: &Path, name: &str, mesh: &Mesh) -> Result<(), String> {
+    let mut stl = format!("solid {}\n", name);
+    for &triangle in &mesh.triangles {
+        let n = normal(mesh, triangle);
+        stl.push_str(&format!(
+            "  facet normal {} {} {}\n    outer loop\n",
+            n[0], n[1], n[2]
+        ));
+        for index in triangle {
+            let v = mesh.vertices[index];
+            stl.push_str(&format!("      vertex {} {} {}\n", v[0], v[1], v[2]));
+        }
+        stl.push_str("    endloop\n  endfacet\n");
+    }
+    stl.push_str(&format!("endsolid {}\n", name));
+    return fs::write(path, stl)
+        .map_err(|e| format!("Cannot write mesh {}: {}", path.display(), e));
+}
+
+pub fn write_obj(path: &Path, name: &str, mesh: &Mesh) -> Result<(), String> {
+    let mut obj = format!("# Units: inches\no {}\n", name);
+    for v in &mesh.vertices {
+        obj.push_str(&format!("v {} {} {}\n", v[0], v[1], v[2]));
+    }
+    for triangle in &mesh.triangles {
+        obj.push_str(&format!(
+            "f {} {} {}\n",
+            triangle[0] + 1,
+            triangle[1] + 1,
+            triangle[2] + 1
+        ));
+    }
+    return fs::write(path, obj)
+        .map_err(|e| format!("Cannot write mesh {}: {}", path.display(), e));
+}
+
+// A beam as a solid, `<dir>/<shape>_beam.stl` and `.obj`.
+pub fn write_beam(dir: &str, beam: Beams) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Cannot create mesh directory {}: {}", dir, e))?;
+    let solid = match beam {
+        Beams::R(a) => extrude(&a.plates(), a.Length),
+        Beams::T(a) => extrude(&a.plates(), a.Length),
+        Beams::I(a) => extrude(&a.plates(), a.Length),
+    };
+    let name = format!("{}_beam", beam.shape());
+    let path = Path::new(dir).join(&name);
+    return write_stl(&path.with_extension("stl"), &name, &solid)
+        .and_then(|_| write_obj(&path.with_extension("obj"), &name, &solid));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beams::Material;
+
+    fn plate(x: f64, y: f64, width: f64, height: f64, flange: bool) -> Plate {
+        return Plate {
+            x,
+            y,
+            width,
+            height,
+            material: Material::Steel1018,
+            flange,
+        };
+    }
+
+    #[test]
+    fn extruded_section_is_closed_with_the_prism_volume() {
+        // A T section, plates centred on x and y with the flange on top of a narrower web, so the caps and walls
+        // have notches to get wrong.
+        let plates = [
+            plate(2.0, 0.25, 4.0, 0.5, true),
+            plate(2.0, 2.0, 0.5, 3.0, false),
+        ];
+        let length = 12.0;
+        let mesh = extrude(&plates, length);
+
+        // Closed and consistently wound: each directed edge once and its reverse once.
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for &[a, b, c] in &mesh.triangles {
+            for edge in [(a, b), (b, c), (c, a)] {
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1, "edge {} {}", a, b);
+            assert_eq!(edges.get(&(b, a)), Some(&1), "edge {} {}", a, b);
+        }
+
+        // Divergence theorem, positive when the triangles face outward.
+        let volume: f64 = mesh
+            .triangles
+            .iter()
+            .map(|&triangle| {
+                let [a, b, c] = triangle.map(|index| mesh.vertices[index]);
+                let n = cross(b, c);
+                return (a[0] * n[0] + a[1] * n[1] + a[2] * n[2]) / 6.0;
+            })
+            .sum();
+        let area: f64 = plates.iter().map(|p| p.width * p.height).sum();
+        assert!((volume - area * length).abs() < 1e-9, "volume {}", volume);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::rng;
+use rand::Rng;
+use std::cell::RefCell;
+
+// Mutations tried between step size updates under the 1/5th rule.
+const ADAPT_TRIALS: u64 = 100;
+// Step size factor per update, the usual choice for the 1/5th rule.
+const ADAPT_FACTOR: f64 = 0.85;
+// Limits on the adapted scale. A population with nothing feasible can keep "succeeding" with ever larger steps.
+const SCALE_MIN: f64 = 0.01;
+const SCALE_MAX: f64 = 10.0;
+
+#[derive(Copy, Clone)]
+pub enum MutationOperator {
+    // Uniform noise over each gene's step, what the simulations always did.
+    Uniform,
+    // Normal noise with a standard deviation of sigma times the gene's range.
+    Gaussian(f64),
+    // Deb's polynomial mutation with distribution index eta over the gene's range.
+    Polynomial(f64),
+}
+
+// What happens to a gene mutated past its bounds.
+#[derive(Copy, Clone)]
+pub enum BoundHandling {
+    Ignore,
+    Clamp,
+    Reflect,
+}
+
+#[derive(Copy, Clone)]
+pub struct MutationSettings {
+    pub operator: MutationOperator,
+    pub bounds: BoundHandling,
+    // Chance that each gene mutates.
+    pub rate: f64,
+    // Scale the steps by Rechenberg's 1/5th success rule.
+    pub one_fifth: bool,
+}
+
+impl Default for MutationSettings {
+    fn default() -> MutationSettings {
+        return MutationSettings {
+            operator: MutationOperator::Uniform,
+            bounds: BoundHandling::Clamp,
+            rate: 1.0,
+            one_fifth: false,
+        };
+    }
+}
+
+impl MutationOperator {
+    // Accepts uniform, gaussian, gaussian:<sigma>, polynomial or polynomial:<eta>.
+    pub fn parse(name: &str) -> Result<MutationOperator, String> {
+        let lower = name.to_lowercase();
+        let mut parts = lower.splitn(2, ':');
+        let parameter = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| format!("Bad mutation parameter {}: {}", value, e))
+        };
+        return match (parts.next(), parts.next()) {
+            (Some("uniform"), None) => Ok(MutationOperator::Uniform),
+            (Some("gaussian"), None) => Ok(MutationOperator::Gaussian(0.05)),
+            (Some("gaussian"), Some(sigma)) => Ok(MutationOperator::Gaussian(parameter(sigma)?)),
+            (Some("polynomial"), None) => Ok(MutationOperator::Polynomial(20.0)),
+            (Some("polynomial"), Some(eta)) => Ok(MutationOperator::Polynomial(parameter(eta)?)),
+            _ => Err(format!("Unknown mutation operator {}", name)),
+        };
+    }
+}
+
+impl BoundHandling {
+    pub fn parse(name: &str) -> Result<BoundHandling, String> {
+        return match name.to_lowercase().as_str() {
+            "ignore" => Ok(BoundHandling::Ignore),
+            "clamp" => Ok(BoundHandling::Clamp),
+            "reflect" => Ok(BoundHandling::Reflect),
+            _ => Err(format!("Unknown bound handling {}", name)),
+        };
+    }
+
+    fn apply(&self, value: f64, (min, max): (f64, f64)) -> f64 {
+        return match self {
+            BoundHandling::Ignore => value,
+            BoundHandling::Clamp => value.max(min).min(max),
+            BoundHandling::Reflect => {
+                let reflected = match value {
+                    v if v < min => min + (min - v),
+                    v if v > max => max - (v - max),
+                    v => v,
+                };
+                // A step longer than the range would reflect out the other side.
+                reflected.max(min).min(max)
+            }
+        };
+    }
+}
+
+impl MutationSettings {
+    pub fn name(&self) -> String {
+        let operator = match self.operator {
+            MutationOperator::Uniform => String::from("uniform"),
+            MutationOperator::Gaussian(sigma) => format!("gaussian (sigma {})", sigma),
+            MutationOperator::Polynomial(eta) => format!("polynomial (eta {})", eta),
+        };
+        let bounds = match self.bounds {
+            BoundHandling::Ignore => "bounds ignored",
+            BoundHandling::Clamp => "clamped to bounds",
+            BoundHandling::Reflect => "reflected at bounds",
+        };
+        let adaptation = match self.one_fifth {
+            true => ", 1/5th rule step size",
+            false => "",
+        };
+        return format!("{}, {}, rate {}{}", operator, bounds, self.rate, adaptation);
+    }
+
+    // `steps` are the uniform operator's amplitudes. Genes with equal bounds are fixed and never move.
+    pub fn mutate(&self, genes: &[f64], bounds: &[(f64, f64)], steps: &[f64]) -> Vec<f64> {
+        let mut rng = rng::thread_rng();
+        let scale = match self.one_fifth {
+            true => step_scale(),
+            false => 1.0,
+        };
+        return genes
+            .iter()
+            .zip(bounds.iter().zip(steps))
+            .map(|(&gene, (&(min, max), &step))| {
+                if min == max || (self.rate < 1.0 && rng.gen::<f64>() >= self.rate) {
+                    return gene;
+                }
+                let range = max - min;
+                let mutated = match self.operator {
+                    MutationOperator::Uniform => gene + (rng.gen::<f64>() - 0.5) * step * scale,
+                    MutationOperator::Gaussian(sigma) => {
+                        gene + rng::normal() * sigma * range * scale
+                    }
+                    MutationOperator::Polynomial(eta) => {
+                        let u: f64 = rng.gen();
+                        let delta = match u < 0.5 {
+                            true => f64::powf(2.0 * u, 1.0 / (eta + 1.0)) - 1.0,
+                            false => 1.0 - f64::powf(2.0 * (1.0 - u), 1.0 / (eta + 1.0)),
+                        };
+                        gene + delta * range * scale
+                    }
+                };
+                self.bounds.apply(mutated, (min, max))
+            })
+            .collect();
+    }
+}
+
+// Step size state for the 1/5th rule, one per simulation thread.
+struct StepSize {
+    scale: f64,
+    successes: u64,
+    trials: u64,
+}
+
+thread_local! {
+    static STEP: RefCell<StepSize> = const {
+        RefCell::new(StepSize {
+            scale: 1.0,
+            successes: 0,
+            trials: 0,
+        })
+    };
+}
+
+fn step_scale() -> f64 {
+    return STEP.with(|step| step.borrow().scale);
+}
+
+// Counts a mutation that did or did not improve on the unmutated child. More than one in five succeeding means
+// the steps are too timid, fewer means they overshoot.
+pub fn record(success: bool) {
+    STEP.with(|step| {
+        let mut step = step.borrow_mut();
+        step.trials += 1;
+        if success {
+            step.successes += 1;
+        }
+        if step.trials >= ADAPT_TRIALS {
+            let ratio = step.successes as f64 / step.trials as f64;
+            if ratio > 0.2 {
+                step.scale /= ADAPT_FACTOR;
+            } else if ratio < 0.2 {
+                step.scale *= ADAPT_FACTOR;
+            }
+            step.scale = step.scale.clamp(SCALE_MIN, SCALE_MAX);
+            step.successes = 0;
+            step.trials = 0;
+        }
+    });
+}
+
+// Step size state as text for a checkpoint: scale, successes, trials.
+pub fn save_state() -> String {
+    return STEP.with(|step| {
+        let step = step.borrow();
+        format!("{} {} {}", step.scale, step.successes, step.trials)
+    });
+}
+
+pub fn restore_state(text: &str) -> Result<(), String> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    let bad = || format!("Bad mutation state {}", text);
+    if fields.len() != 3 {
+        return Err(bad());
+    }
+    let scale = fields[0].parse::<f64>().map_err(|_| bad())?;
+    let successes = fields[1].parse::<u64>().map_err(|_| bad())?;
+    let trials = fields[2].parse::<u64>().map_err(|_| bad())?;
+    STEP.with(|step| {
+        *step.borrow_mut() = StepSize {
+            scale,
+            successes,
+            trials,
+        }
+    });
+    return Ok(());
+}
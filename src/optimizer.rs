@@ -0,0 +1,431 @@
+use crate::fitness::BeamFitness;
+use crate::ga::{self, Genes, Outcome};
+use crate::rng;
+use crate::stopping::StopReason;
+use rand::Rng;
+use rsgenetic::pheno::Phenotype;
+use rsgenetic::sim::select::Selector;
+
+// Annealing cools by this factor over the full run, whatever the starting temperature.
+const ANNEALING_COOLING: f64 = 1.0e-4;
+// Annealing moves are this fraction of each gene's range at the starting temperature, shrinking with the square
+// root of the temperature.
+const ANNEALING_STEP: f64 = 0.1;
+
+// Global search method run by every simulation. Whatever the method, the population is its whole state, so
+// checkpoints and resumed runs work the same for all of them. Material and shape genes only change under the GA,
+// the other methods move the continuous genes of each individual.
+#[derive(Copy, Clone)]
+pub enum Optimizer {
+    Genetic,
+    // Bare-bones particle swarm: the population holds each particle's personal best, and every generation a new
+    // position is drawn around the midpoint of the personal and swarm bests with a spread of their distance.
+    Swarm,
+    // DE/rand/1/bin with differential weight F and crossover rate CR.
+    Differential(f64, f64),
+    // One Metropolis chain per individual, starting at this temperature in units of relative score change.
+    Annealing(f64),
+}
+
+impl Default for Optimizer {
+    fn default() -> Optimizer {
+        return Optimizer::Genetic;
+    }
+}
+
+// Parameters only the GA uses: how parents are picked and how many of the best sit out the kill off.
+pub struct Breeding<'a, T> {
+    pub selector: &'a dyn Selector<T, BeamFitness>,
+    pub elites: usize,
+}
+
+impl Optimizer {
+    // Accepts ga, pso, de, de:<f>:<cr>, sa or sa:<temperature>.
+    pub fn parse(name: &str) -> Result<Optimizer, String> {
+        let lower = name.to_lowercase();
+        let parts: Vec<&str> = lower.split(':').collect();
+        let parameter = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| format!("Bad optimizer parameter {}: {}", value, e))
+        };
+        return match parts[..] {
+            ["ga"] => Ok(Optimizer::Genetic),
+            ["pso"] => Ok(Optimizer::Swarm),
+            ["de"] => Ok(Optimizer::Differential(0.5, 0.9)),
+            ["de", weight, rate] => {
+                let (weight, rate) = (parameter(weight)?, parameter(rate)?);
+                match (
+                    weight > 0.0 && weight.is_finite(),
+                    (0.0..=1.0).contains(&rate),
+                ) {
+                    (true, true) => Ok(Optimizer::Differential(weight, rate)),
+                    (false, _) => Err(format!(
+                        "Differential evolution weight F {} must be greater than 0",
+                        weight
+                    )),
+                    (_, false) => Err(format!(
+                        "Differential evolution crossover rate CR {} must be between 0 and 1",
+                        rate
+                    )),
+                }
+            }
+            ["sa"] => Ok(Optimizer::Annealing(0.1)),
+            ["sa", temperature] => match parameter(temperature)? {
+                t if t > 0.0 && t.is_finite() => Ok(Optimizer::Annealing(t)),
+                t => Err(format!(
+                    "Annealing temperature {} must be greater than 0",
+                    t
+                )),
+            },
+            _ => Err(format!("Unknown optimizer {}", name)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            Optimizer::Genetic => String::from("genetic algorithm"),
+            Optimizer::Swarm => String::from("bare-bones particle swarm"),
+            Optimizer::Differential(weight, rate) => {
+                format!("differential evolution (F {}, CR {})", weight, rate)
+            }
+            Optimizer::Annealing(temperature) => {
+                format!("simulated annealing (temperature {})", temperature)
+            }
+        };
+    }
+
+    // Runs generations `first_generation + 1..=iterations` like ga::evolve, which the GA hands straight on to.
+    // `bounds` gives the gene bounds of an individual, `breeding` only matters to the GA.
+    pub fn run<T>(
+        &self,
+        population: &mut Vec<T>,
+        breeding: Breeding<T>,
+        first_generation: u64,
+        iterations: u64,
+        bounds: &dyn Fn(&T) -> Vec<(f64, f64)>,
+        report: &mut dyn FnMut(u64, &[T]) -> Option<StopReason>,
+    ) -> Result<Outcome<T>, String>
+    where
+        T: Phenotype<BeamFitness> + Genes,
+    {
+        if let Optimizer::Genetic = self {
+            return ga::evolve(
+                population,
+                breeding.selector,
+                breeding.elites,
+                first_generation,
+                iterations,
+                report,
+            );
+        }
+        if population.is_empty() {
+            return Err(String::from(
+                "Tried to run an optimizer without a population",
+            ));
+        }
+        let mut generations = first_generation;
+        let mut stop = StopReason::Iterations;
+        for generation in first_generation + 1..=iterations {
+            match *self {
+                Optimizer::Swarm => swarm_step(population, bounds),
+                Optimizer::Differential(weight, rate) => {
+                    differential_step(population, bounds, weight, rate)
+                }
+                Optimizer::Annealing(temperature) => {
+                    let progress = generation as f64 / iterations as f64;
+                    anneal_step(
+                        population,
+                        bounds,
+                        temperature * f64::powf(ANNEALING_COOLING, progress),
+                        f64::powf(ANNEALING_COOLING, progress / 2.0),
+                    )
+                }
+                Optimizer::Genetic => {}
+            }
+            generations = generation;
+            if let Some(reason) = report(generation, population) {
+                stop = reason;
+                break;
+            }
+        }
+        return Ok(Outcome {
+            best: population
+                .iter()
+                .max_by_key(|x| x.fitness())
+                .unwrap()
+                .clone(),
+            generations,
+            stop,
+        });
+    }
+}
+
+fn clamp(genes: &mut [f64], bounds: &[(f64, f64)]) {
+    for (gene, &(min, max)) in genes.iter_mut().zip(bounds) {
+        *gene = gene.clamp(min, max);
+    }
+}
+
+// Individuals grouped by genome length, with the group of each. A mixed shape population only combines like
+// shapes.
+fn peers<T: Genes>(population: &[T]) -> (Vec<Vec<usize>>, Vec<usize>) {
+    let mut lengths: Vec<usize> = Vec::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: Vec<usize> = Vec::with_capacity(population.len());
+    for (index, individual) in population.iter().enumerate() {
+        let length = individual.genes().len();
+        let group = match lengths.iter().position(|&l| l == length) {
+            Some(group) => group,
+            None => {
+                lengths.push(length);
+                groups.push(Vec::new());
+                groups.len() - 1
+            }
+        };
+        groups[group].push(index);
+        group_of.push(group);
+    }
+    return (groups, group_of);
+}
+
+// Replaces each individual by its trial design when the trial scores at least as well.
+fn replace_if_better<T>(population: &mut [T], scores: &[BeamFitness], trials: Vec<Option<T>>)
+where
+    T: Phenotype<BeamFitness>,
+{
+    for (index, trial) in trials.into_iter().enumerate() {
+        if let Some(trial) = trial {
+            if trial.fitness() >= scores[index] {
+                population[index] = trial;
+            }
+        }
+    }
+}
+
+fn swarm_step<T>(population: &mut [T], bounds: &dyn Fn(&T) -> Vec<(f64, f64)>)
+where
+    T: Phenotype<BeamFitness> + Genes,
+{
+    let mut rng = rng::thread_rng();
+    let scores: Vec<BeamFitness> = population.iter().map(|x| x.fitness()).collect();
+    let (groups, group_of) = peers(population);
+    let leaders: Vec<Vec<f64>> = groups
+        .iter()
+        .map(|group| {
+            let leader = *group.iter().max_by_key(|&&index| scores[index]).unwrap();
+            population[leader].genes()
+        })
+        .collect();
+    let trials: Vec<Option<T>> = (0..population.len())
+        .map(|index| {
+            let best = &leaders[group_of[index]];
+            let mut genes = population[index].genes();
+            // Half of the genes stay at the personal best, which keeps the swarm from collapsing too early.
+            for (gene, &lead) in genes.iter_mut().zip(best) {
+                if rng.gen::<bool>() {
+                    *gene = (*gene + lead) / 2.0 + rng::normal() * (*gene - lead).abs();
+                }
+            }
+            clamp(&mut genes, &bounds(&population[index]));
+            return Some(population[index].with_genes(&genes));
+        })
+        .collect();
+    replace_if_better(population, &scores, trials);
+}
+
+fn differential_step<T>(
+    population: &mut [T],
+    bounds: &dyn Fn(&T) -> Vec<(f64, f64)>,
+    weight: f64,
+    rate: f64,
+) where
+    T: Phenotype<BeamFitness> + Genes,
+{
+    let mut rng = rng::thread_rng();
+    let scores: Vec<BeamFitness> = population.iter().map(|x| x.fitness()).collect();
+    let (groups, group_of) = peers(population);
+    let trials: Vec<Option<T>> = (0..population.len())
+        .map(|index| {
+            let others = &groups[group_of[index]];
+            if others.len() < 4 {
+                return None;
+            }
+            // Three distinct donors other than the target.
+            let mut donors: Vec<usize> = Vec::with_capacity(3);
+            while donors.len() < 3 {
+                let donor = others[rng.gen_range(0..others.len())];
+                if donor != index && !donors.contains(&donor) {
+                    donors.push(donor);
+                }
+            }
+            let (a, b, c) = (
+                population[donors[0]].genes(),
+                population[donors[1]].genes(),
+                population[donors[2]].genes(),
+            );
+            let mut genes = population[index].genes();
+            // At least one gene always comes from the mutant.
+            let forced = rng.gen_range(0..genes.len());
+            for (j, gene) in genes.iter_mut().enumerate() {
+                if j == forced || rng.gen::<f64>() < rate {
+                    *gene = a[j] + weight * (b[j] - c[j]);
+                }
+            }
+            clamp(&mut genes, &bounds(&population[index]));
+            return Some(population[index].with_genes(&genes));
+        })
+        .collect();
+    replace_if_better(population, &scores, trials);
+}
+
+// One Metropolis move per chain. Score changes are taken relative to the current score, so the temperature means
+// the same for any objective and constraint handling.
+fn anneal_step<T>(
+    population: &mut [T],
+    bounds: &dyn Fn(&T) -> Vec<(f64, f64)>,
+    temperature: f64,
+    step: f64,
+) where
+    T: Phenotype<BeamFitness> + Genes,
+{
+    let mut rng = rng::thread_rng();
+    for individual in population.iter_mut() {
+        let current = individual.fitness().value();
+        let limits = bounds(individual);
+        let mut genes = individual.genes();
+        for (gene, &(min, max)) in genes.iter_mut().zip(&limits) {
+            *gene += rng::normal() * (max - min) * ANNEALING_STEP * step;
+        }
+        clamp(&mut genes, &limits);
+        let candidate = individual.with_genes(&genes);
+        let change = (candidate.fitness().value() - current) / current.abs().max(1.0);
+        if change >= 0.0 || rng.gen::<f64>() < (change / temperature).exp() {
+            *individual = candidate;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsgenetic::sim::select::UnstableMaximizeSelector;
+
+    // Maximize -(x - 1)^2 - (y - 1)^2 - (z - 1)^2 over [-5, 5] in each gene.
+    #[derive(Clone)]
+    struct Sphere(Vec<f64>);
+
+    impl Phenotype<BeamFitness> for Sphere {
+        fn fitness(&self) -> BeamFitness {
+            return BeamFitness::new(-self.0.iter().map(|x| (x - 1.0) * (x - 1.0)).sum::<f64>());
+        }
+
+        fn crossover(&self, _: &Sphere) -> Sphere {
+            return self.clone();
+        }
+
+        fn mutate(&self) -> Sphere {
+            return self.clone();
+        }
+    }
+
+    impl Genes for Sphere {
+        fn genes(&self) -> Vec<f64> {
+            return self.0.clone();
+        }
+
+        fn with_genes(&self, genes: &[f64]) -> Sphere {
+            return Sphere(genes.to_vec());
+        }
+
+        fn gene_names(&self) -> Vec<&'static str> {
+            return vec!["x", "y", "z"];
+        }
+    }
+
+    // Best and mean score of a seeded random population before and after 50 generations.
+    fn improvement(optimizer: Optimizer) -> ((f64, f64), (f64, f64)) {
+        rng::seed(7);
+        let mut rng = rng::thread_rng();
+        let mut population: Vec<Sphere> = (0..20)
+            .map(|_| Sphere((0..3).map(|_| rng.gen_range(-5.0..5.0)).collect()))
+            .collect();
+        let summary = |population: &[Sphere]| {
+            let scores: Vec<f64> = population.iter().map(|x| x.fitness().value()).collect();
+            return (
+                scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                scores.iter().sum::<f64>() / scores.len() as f64,
+            );
+        };
+        let before = summary(&population);
+        let selector = UnstableMaximizeSelector::new(8);
+        optimizer
+            .run(
+                &mut population,
+                Breeding {
+                    selector: &selector,
+                    elites: 0,
+                },
+                0,
+                50,
+                &|_| vec![(-5.0, 5.0); 3],
+                &mut |_, _| None,
+            )
+            .unwrap();
+        return (before, summary(&population));
+    }
+
+    fn assert_improves(optimizer: Optimizer) {
+        let ((best_before, mean_before), (best_after, mean_after)) = improvement(optimizer);
+        assert!(
+            best_after > best_before && mean_after > mean_before,
+            "{}: best {} -> {}, mean {} -> {}",
+            optimizer.name(),
+            best_before,
+            best_after,
+            mean_before,
+            mean_after
+        );
+        // Every method gets well inside the starting spread, which is tens of units off the optimum.
+        assert!(
+            best_after > -0.5,
+            "{}: best {}",
+            optimizer.name(),
+            best_after
+        );
+    }
+
+    #[test]
+    fn swarm_improves_a_population() {
+        assert_improves(Optimizer::Swarm);
+    }
+
+    #[test]
+    fn differential_evolution_improves_a_population() {
+        assert_improves(Optimizer::Differential(0.5, 0.9));
+    }
+
+    #[test]
+    fn annealing_improves_a_population() {
+        assert_improves(Optimizer::Annealing(0.1));
+    }
+
+    #[test]
+    fn parameters_out_of_range_are_rejected() {
+        for name in [
+            "de:0.5:3",
+            "de:0.5:-0.1",
+            "de:0:0.9",
+            "de:-0.5:0.9",
+            "sa:0",
+            "sa:-1",
+            "sa:nan",
+        ] {
+            assert!(Optimizer::parse(name).is_err(), "{}", name);
+        }
+        assert!(matches!(
+            Optimizer::parse("de:0.7:1"),
+            Ok(Optimizer::Differential(f, cr)) if f == 0.7 && cr == 1.0
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::analysis::{get_deflection, get_factor_of_safety, get_flight_hours};
+use crate::beams::{Beams, Cost, Material, Stress, Weight};
+use crate::config;
+use crate::constraints::Evaluate;
+use crate::ga::Genes;
+use std::fs;
+use std::path::Path;
+
+// Grid points along each plotted gene.
+pub const PLOT_POINTS: usize = 61;
+// Contour lines drawn per map.
+const LEVELS: usize = 10;
+const WIDTH: f64 = 760.0;
+const HEIGHT: f64 = 560.0;
+// Plot area inside the image, leaving room for the axes, title and level legend.
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 600.0;
+const TOP: f64 = 50.0;
+const BOTTOM: f64 = 490.0;
+// Colours of the lowest to highest level, interpolated in between.
+const PALETTE: [(f64, f64, f64); 5] = [
+    (68.0, 1.0, 84.0),
+    (59.0, 82.0, 139.0),
+    (33.0, 145.0, 140.0),
+    (94.0, 201.0, 98.0),
+    (253.0, 231.0, 37.0),
+];
+
+// One analysis output sampled over a grid of two design variables. `values[j][i]` and `feasible[j][i]` are at
+// `xs[i]`, `ys[j]`.
+pub struct Map {
+    pub title: String,
+    pub x_name: String,
+    pub y_name: String,
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub values: Vec<Vec<f64>>,
+    pub feasible: Vec<Vec<bool>>,
+    // Contour levels spaced by ratio rather than difference, for outputs spanning orders of magnitude.
+    pub logarithmic: bool,
+    pub marker: (f64, f64),
+}
+
+// Accepts <gene>:<gene>, the horizontal then vertical axis.
+pub fn parse_axes(text: &str) -> Result<(String, String), String> {
+    let parts: Vec<&str> = text.split(':').collect();
+    return match parts[..] {
+        [x, y] if !x.is_empty() && !y.is_empty() && x != y => {
+            Ok((x.to_lowercase(), y.to_lowercase()))
+        }
+        _ => Err(format!(
+            "Bad plot axes {}, expected two different genes as <gene>:<gene>",
+            text
+        )),
+    };
+}
+
+fn colour(fraction: f64) -> String {
+    let position = fraction.clamp(0.0, 1.0) * (PALETTE.len() - 1) as f64;
+    let low = (position.floor() as usize).min(PALETTE.len() - 2);
+    let t = position - low as f64;
+    let (a, b) = (PALETTE[low], PALETTE[low + 1]);
+    return format!(
+        "rgb({},{},{})",
+        (a.0 + (b.0 - a.0) * t).round(),
+        (a.1 + (b.1 - a.1) * t).round(),
+        (a.2 + (b.2 - a.2) * t).round()
+    );
+}
+
+// Evenly spaced levels strictly inside the range of the finite values.
+fn levels(values: &[Vec<f64>], logarithmic: bool) -> Vec<f64> {
+    let finite: Vec<f64> = values
+        .iter()
+        .flatten()
+        .cloned()
+        .filter(|v| v.is_finite() && (!logarithmic || *v > 0.0))
+        .collect();
+    if finite.is_empty() {
+        return Vec::new();
+    }
+    let scale = |v: f64| match logarithmic {
+        true => v.log10(),
+        false => v,
+    };
+    let min = finite
+        .iter()
+        .cloned()
+        .map(scale)
+        .fold(f64::INFINITY, f64::min);
+    let max = finite
+        .iter()
+        .cloned()
+        .map(scale)
+        .fold(f64::NEG_INFINITY, f64::max);
+    if min == max {
+        return Vec::new();
+    }
+    return (1..=LEVELS)
+        .map(|k| min + (max - min) * k as f64 / (LEVELS + 1) as f64)
+        .map(|level| match logarithmic {
+            true => f64::powf(10.0, level),
+            false => level,
+        })
+        .collect();
+}
+
+// Line segments where the surface crosses `level`, by marching squares in grid coordinates (i, j). Saddle cells
+// are split by the value at their centre.
+fn contour(values: &[Vec<f64>], level: f64) -> Vec<((f64, f64), (f64, f64))> {
+    let mut segments = Vec::new();
+    for j in 0..values.len().saturating_sub(1) {
+        for i in 0..values[j].len().saturating_sub(1) {
+            // Corners anticlockwise from the bottom left.
+            let corners = [
+                (i as f64, j as f64, values[j][i]),
+                (i as f64 + 1.0, j as f64, values[j][i + 1]),
+                (i as f64 + 1.0, j as f64 + 1.0, values[j + 1][i + 1]),
+                (i as f64, j as f64 + 1.0, values[j + 1][i]),
+            ];
+            if corners.iter().any(|c| !c.2.is_finite()) {
+                continue;
+            }
+            // Where each edge crosses the level, edge k joining corners k and k + 1.
+            let crossing = |k: usize| {
+                let (a, b) = (corners[k], corners[(k + 1) % 4]);
+                let t = (level - a.2) / (b.2 - a.2);
+                return (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+            };
+            let crossed: Vec<usize> = (0..4)
+                .filter(|&k| (corners[k].2 >= level) != (corners[(k + 1) % 4].2 >= level))
+                .collect();
+            match crossed.len() {
+                2 => segments.push((crossing(crossed[0]), crossing(crossed[1]))),
+                4 => {
+                    let centre = corners.iter().map(|c| c.2).sum::<f64>() / 4.0;
+                    // Join the edges around the corners on the other side of the level from the centre.
+                    match (centre >= level) == (corners[0].2 >= level) {
+                        true => {
+                            segments.push((crossing(0), crossing(1)));
+                            segments.push((crossing(2), crossing(3)));
+                        }
+                        false => {
+                            segments.push((crossing(3), crossing(0)));
+                            segments.push((crossing(1), crossing(2)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    return segments;
+}
+
+fn format_value(value: f64) -> String {
+    return match value.abs() >= 1.0e5 || (value != 0.0 && value.abs() < 1.0e-2) {
+        true => format!("{:.3e}", value),
+        false => format!("{:.3}", value),
+    };
+}
+
+pub fn write_svg(path: &Path, map: &Map) -> Result<(), String> {
+    let (x_min, x_max) = (map.xs[0], map.xs[map.xs.len() - 1]);
+    let (y_min, y_max) = (map.ys[0], map.ys[map.ys.len() - 1]);
+    let x_of = |x: f64| LEFT + (x - x_min) / (x_max - x_min) * (RIGHT - LEFT);
+    let y_of = |y: f64| BOTTOM - (y - y_min) / (y_max - y_min) * (BOTTOM - TOP);
+    // Grid coordinates to image coordinates, for contour segments.
+    let grid_x = |i: f64| {
+        let low = (i.floor() as usize).min(map.xs.len() - 2);
+        return x_of(map.xs[low] + (i - low as f64) * (map.xs[low + 1] - map.xs[low]));
+    };
+    let grid_y = |j: f64| {
+        let low = (j.floor() as usize).min(map.ys.len() - 2);
+        return y_of(map.ys[low] + (j - low as f64) * (map.ys[low + 1] - map.ys[low]));
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        WIDTH, HEIGHT
+    );
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n<text x=\"{}\" y=\"28\" font-size=\"16\" text-anchor=\"middle\">{}</text>\n",
+        WIDTH,
+        HEIGHT,
+        (LEFT + RIGHT) / 2.0,
+        map.title
+    ));
+
+    // Infeasible cells shaded, each cell centred on its grid point and each run of them along a row in one rectangle.
+    svg.push_str("<g fill=\"#999999\" fill-opacity=\"0.45\">\n");
+    for (j, row) in map.feasible.iter().enumerate() {
+        let mut i = 0;
+        while i < row.len() {
+            if row[i] {
+                i += 1;
+                continue;
+            }
+            let first = i;
+            while i < row.len() && !row[i] {
+                i += 1;
+            }
+            let last = i - 1;
+            let left = x_of((map.xs[first.saturating_sub(1)] + map.xs[first]) / 2.0);
+            let right = x_of((map.xs[last] + map.xs[(last + 1).min(map.xs.len() - 1)]) / 2.0);
+            let top = y_of((map.ys[j] + map.ys[(j + 1).min(map.ys.len() - 1)]) / 2.0);
+            let bottom = y_of((map.ys[j.saturating_sub(1)] + map.ys[j]) / 2.0);
+            svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>\n",
+                left,
+                top,
+                right - left,
+                bottom - top
+            ));
+        }
+    }
+    svg.push_str("</g>\n");
+
+    let levels = levels(&map.values, map.logarithmic);
+    for (k, &level) in levels.iter().enumerate() {
+        let fraction = k as f64 / (levels.len().max(2) - 1) as f64;
+        let mut data = String::new();
+        for ((i0, j0), (i1, j1)) in contour(&map.values, level) {
+            data.push_str(&format!(
+                "M{:.2} {:.2}L{:.2} {:.2}",
+                grid_x(i0),
+                grid_y(j0),
+                grid_x(i1),
+                grid_y(j1)
+            ));
+        }
+        svg.push_str(&format!(
+            "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>\n",
+            data,
+            colour(fraction)
+        ));
+        let y = TOP + 20.0 * k as f64;
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"3\"/><text x=\"{}\" y=\"{}\">{}</text>\n",
+            RIGHT + 20.0,
+            y,
+            RIGHT + 45.0,
+            y,
+            colour(fraction),
+            RIGHT + 52.0,
+            y + 4.0,
+            format_value(level)
+        ));
+    }
+    let legend = TOP + 20.0 * levels.len() as f64;
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"25\" height=\"12\" fill=\"#999999\" fill-opacity=\"0.45\"/><text x=\"{}\" y=\"{}\">infeasible</text>\n",
+        RIGHT + 20.0,
+        legend,
+        RIGHT + 52.0,
+        legend + 10.0
+    ));
+    svg.push_str(&format!(
+        "<circle cx=\"{}\" cy=\"{}\" r=\"5\" fill=\"none\" stroke=\"red\" stroke-width=\"2\"/><text x=\"{}\" y=\"{}\">optimum</text>\n",
+        RIGHT + 32.0,
+        legend + 26.0,
+        RIGHT + 52.0,
+        legend + 30.0
+    ));
+
+    // Axes with five ticks each.
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>\n",
+        LEFT,
+        TOP,
+        RIGHT - LEFT,
+        BOTTOM - TOP
+    ));
+    for k in 0..5 {
+        let x = x_min + (x_max - x_min) * k as f64 / 4.0;
+        let y = y_min + (y_max - y_min) * k as f64 / 4.0;
+        svg.push_str(&format!(
+            "<line x1=\"{0:.2}\" y1=\"{1}\" x2=\"{0:.2}\" y2=\"{2}\" stroke=\"black\"/><text x=\"{0:.2}\" y=\"{3}\" text-anchor=\"middle\">{4}</text>\n",
+            x_of(x),
+            BOTTOM,
+            BOTTOM + 5.0,
+            BOTTOM + 18.0,
+            format_value(x)
+        ));
+        svg.push_str(&format!(
+            "<line x1=\"{0}\" y1=\"{1:.2}\" x2=\"{2}\" y2=\"{1:.2}\" stroke=\"black\"/><text x=\"{3}\" y=\"{4:.2}\" text-anchor=\"end\">{5}</text>\n",
+            LEFT - 5.0,
+            y_of(y),
+            LEFT,
+            LEFT - 8.0,
+            y_of(y) + 4.0,
+            format_value(y)
+        ));
+    }
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n<text x=\"20\" y=\"{}\" text-anchor=\"middle\" transform=\"rotate(-90 20 {})\">{}</text>\n",
+        (LEFT + RIGHT) / 2.0,
+        BOTTOM + 40.0,
+        map.x_name,
+        (TOP + BOTTOM) / 2.0,
+        (TOP + BOTTOM) / 2.0,
+        map.y_name
+    ));
+
+    let (marker_x, marker_y) = map.marker;
+    svg.push_str(&format!(
+        "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"6\" fill=\"none\" stroke=\"red\" stroke-width=\"2\"/>\n</svg>\n",
+        x_of(marker_x),
+        y_of(marker_y)
+    ));
+    return fs::write(path, svg)
+        .map_err(|e| format!("Cannot write plot {}: {}", path.display(), e));
+}
+
+// Contour maps of weight, FOS, deflection and fatigue life over two genes around a design, with its other genes
+// held fixed within `bounds`, written to `<dir>/<shape>_<material>_<output>.svg`. Each gene spans its bounds, widened to take in
+// the design, which is marked.
+fn plot_beam<T>(
+    dir: &str,
+    shape: &str,
+    material: Material,
+    beam: T,
+    bounds: &[(f64, f64)],
+) -> Result<(), String>
+where
+    T: Genes + Evaluate + Stress + Weight + Cost + Copy,
+{
+    let names = beam.gene_names();
+    let genes = beam.genes();
+    let (x_name, y_name) = match &config::get().plot_axes {
+        Some((x, y)) => (x.clone(), y.clone()),
+        None => {
+            let free: Vec<&str> = names
+                .iter()
+                .zip(bounds)
+                .filter(|(_, &(min, max))| max > min)
+                .map(|(&name, _)| name)
+                .collect();
+            match free[..] {
+                [x, y, ..] => (x.to_string(), y.to_string()),
+                _ => {
+                    return Err(format!(
+                        "Cannot plot a {} beam, it has {} free genes and a plot needs two",
+                        shape,
+                        free.len()
+                    ))
+                }
+            }
+        }
+    };
+    let mut axes: Vec<(usize, Vec<f64>)> = Vec::new();
+    for name in [&x_name, &y_name] {
+        let index = names.iter().position(|n| n == name).ok_or_else(|| {
+            format!(
+                "Cannot plot gene {} of a {} beam, expected one of {}",
+                name,
+                shape,
+                names.join(", ")
+            )
+        })?;
+        let min = bounds[index].0.min(genes[index]);
+        let max = bounds[index].1.max(genes[index]);
+        if min == max {
+            return Err(format!(
+                "Cannot plot {}, it is fixed at {}, give it bounds to vary it",
+                name, min
+            ));
+        }
+        let step = (max - min) / (PLOT_POINTS - 1) as f64;
+        axes.push((
+            index,
+            (0..PLOT_POINTS).map(|i| min + i as f64 * step).collect(),
+        ));
+    }
+
+    // Outputs at every grid point, rows along the vertical axis.
+    let outputs = ["weight", "fos", "deflection", "flight_hours"];
+    let mut values: Vec<Vec<Vec<f64>>> = vec![Vec::new(); outputs.len()];
+    let mut feasible: Vec<Vec<bool>> = Vec::new();
+    for &y in &axes[1].1 {
+        let mut rows: Vec<Vec<f64>> = vec![Vec::new(); outputs.len()];
+        let mut feasible_row: Vec<bool> = Vec::new();
+        for &x in &axes[0].1 {
+            let mut point = genes.clone();
+            point[axes[0].0] = x;
+            point[axes[1].0] = y;
+            let design = beam.with_genes(&point);
+            let sample = [
+                design.weight(),
+                get_factor_of_safety(&design),
+                get_deflection(&design),
+                get_flight_hours(&design),
+            ];
+            for (row, value) in rows.iter_mut().zip(sample) {
+                row.push(value);
+            }
+            feasible_row.push(design.evaluation().is_feasible());
+        }
+        for (output, row) in values.iter_mut().zip(rows) {
+            output.push(row);
+        }
+        feasible.push(feasible_row);
+    }
+
+    for (output, values) in outputs.iter().zip(values) {
+        let map = Map {
+            title: format!("{} of {} {:?} beam", output, shape, material),
+            x_name: x_name.clone(),
+            y_name: y_name.clone(),
+            xs: axes[0].1.clone(),
+            ys: axes[1].1.clone(),
+            values,
+            feasible: feasible.clone(),
+            logarithmic: *output == "flight_hours",
+            marker: (genes[axes[0].0], genes[axes[1].0]),
+        };
+        let path = Path::new(dir).join(format!("{}_{:?}_{}.svg", shape, material, output));
+        write_svg(&path, &map)?;
+    }
+    return Ok(());
+}
+
+// Contour maps around a beam in `dir`, `bounds` being its gene bounds.
+pub fn write_beam(dir: &str, beam: Beams, bounds: &[(f64, f64)]) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Cannot create plot directory {}: {}", dir, e))?;
+    return match beam {
+        Beams::R(a) => plot_beam(dir, beam.shape(), a.Material, a, bounds),
+        Beams::T(a) => plot_beam(dir, beam.shape(), a.Material, a, bounds),
+        Beams::I(a) => plot_beam(dir, beam.shape(), a.Material, a, bounds),
+    };
+}
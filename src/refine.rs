@@ -0,0 +1,236 @@
+use crate::config;
+use crate::constraints::{Evaluate, Evaluation};
+use crate::fitness::BeamFitness;
+use crate::ga::Genes;
+
+// Augmented Lagrangian updates of the multiplier and penalty, each followed by a descent on the Lagrangian.
+const OUTER_ITERATIONS: usize = 10;
+const INNER_ITERATIONS: usize = 50;
+// Steps and finite differences are in gene ranges, so 0.05 is 5% of a gene's bounds.
+const STEP_MAX: f64 = 0.05;
+const STEP_MIN: f64 = 1.0e-9;
+const DIFFERENCE: f64 = 1.0e-6;
+const PENALTY_INITIAL: f64 = 100.0;
+const PENALTY_GROWTH: f64 = 10.0;
+// Armijo condition, the fraction of the predicted decrease a step has to achieve.
+const SUFFICIENT_DECREASE: f64 = 1.0e-4;
+
+pub struct Refinement<T> {
+    pub design: T,
+    pub before: Evaluation,
+    pub after: Evaluation,
+    pub evaluations: usize,
+}
+
+// Genes with some room to move, scaled to [0, 1] over their bounds.
+struct Space<'a, T> {
+    design: &'a T,
+    genes: Vec<f64>,
+    bounds: &'a [(f64, f64)],
+    free: Vec<usize>,
+}
+
+impl<'a, T: Genes> Space<'a, T> {
+    fn to_unit(&self, genes: &[f64]) -> Vec<f64> {
+        return self
+            .free
+            .iter()
+            .map(|&i| {
+                ((genes[i] - self.bounds[i].0) / (self.bounds[i].1 - self.bounds[i].0))
+                    .clamp(0.0, 1.0)
+            })
+            .collect();
+    }
+
+    fn design_at(&self, unit: &[f64]) -> T {
+        let mut genes = self.genes.clone();
+        for (&i, &u) in self.free.iter().zip(unit) {
+            genes[i] = self.bounds[i].0 + u.clamp(0.0, 1.0) * (self.bounds[i].1 - self.bounds[i].0);
+        }
+        return self.design.with_genes(&genes);
+    }
+}
+
+// Polishes a design with a bound constrained augmented Lagrangian, minimizing -objective + lambda * violation +
+// penalty / 2 * violation^2 by steepest descent on finite difference gradients. The violation is the sum of the
+// relative shortfalls, 0 exactly when every requirement is met. The iterates approach the requirements from
+// outside, so the result is the best design visited by the run's own fitness rather than the last.
+pub fn refine<T>(design: &T, bounds: &[(f64, f64)]) -> Refinement<T>
+where
+    T: Genes + Evaluate + Clone,
+{
+    let genes = design.genes();
+    let space = Space {
+        design,
+        free: (0..genes.len())
+            .filter(|&i| bounds[i].1 > bounds[i].0)
+            .collect(),
+        genes,
+        bounds,
+    };
+    let before = design.evaluation();
+    let scale = before.objective.abs().max(1.0);
+    let fitness = |evaluation: Evaluation| {
+        return BeamFitness::new(config::get().constraint_handling.fitness(evaluation));
+    };
+    let mut evaluations = 0;
+    let mut evaluate = |unit: &[f64]| {
+        evaluations += 1;
+        return space.design_at(unit).evaluation();
+    };
+
+    let mut unit = space.to_unit(&space.genes);
+    let mut best: Option<(Vec<f64>, Evaluation)> = None;
+    let mut best_fitness = fitness(before);
+    let mut multiplier = 0.0;
+    let mut penalty = PENALTY_INITIAL;
+    let mut violation = before.violation;
+    for _ in 0..OUTER_ITERATIONS {
+        let merit = |evaluation: Evaluation| {
+            return -evaluation.objective / scale
+                + multiplier * evaluation.violation
+                + penalty / 2.0 * evaluation.violation * evaluation.violation;
+        };
+        let mut current = merit(evaluate(&unit));
+        let mut step = STEP_MAX;
+        for _ in 0..INNER_ITERATIONS {
+            // Central differences, one sided within a difference of a bound where design_at would clamp the far
+            // point, dropping genes the descent direction would push past their bounds.
+            let gradient: Vec<f64> = (0..unit.len())
+                .map(|i| {
+                    let mut forward = unit.clone();
+                    let mut backward = unit.clone();
+                    forward[i] += DIFFERENCE;
+                    backward[i] -= DIFFERENCE;
+                    let g = match (backward[i] < 0.0, forward[i] > 1.0) {
+                        (true, _) => (merit(evaluate(&forward)) - current) / DIFFERENCE,
+                        (_, true) => (current - merit(evaluate(&backward))) / DIFFERENCE,
+                        _ => {
+                            (merit(evaluate(&forward)) - merit(evaluate(&backward)))
+                                / (2.0 * DIFFERENCE)
+                        }
+                    };
+                    return match (unit[i] <= 0.0 && g > 0.0) || (unit[i] >= 1.0 && g < 0.0) {
+                        true => 0.0,
+                        false => g,
+                    };
+                })
+                .collect();
+            let norm = gradient.iter().map(|g| g * g).sum::<f64>().sqrt();
+            if !norm.is_finite() || norm == 0.0 {
+                break;
+            }
+
+            // Backtrack along the normalized steepest descent direction until the merit drops enough.
+            let mut accepted = None;
+            while step >= STEP_MIN {
+                let candidate: Vec<f64> = unit
+                    .iter()
+                    .zip(&gradient)
+                    .map(|(u, g)| (u - step * g / norm).clamp(0.0, 1.0))
+                    .collect();
+                let evaluation = evaluate(&candidate);
+                let value = merit(evaluation);
+                if value <= current - SUFFICIENT_DECREASE * step * norm {
+                    if fitness(evaluation) > best_fitness {
+                        best_fitness = fitness(evaluation);
+                        best = Some((candidate.clone(), evaluation));
+                    }
+                    accepted = Some((candidate, value));
+                    break;
+                }
+                step /= 2.0;
+            }
+            match accepted {
+                Some((candidate, value)) => {
+                    unit = candidate;
+                    current = value;
+                    step = (step * 2.0).min(STEP_MAX);
+                }
+                None => break,
+            }
+        }
+
+        let latest = evaluate(&unit).violation;
+        multiplier += penalty * latest;
+        if latest > 0.25 * violation {
+            penalty *= PENALTY_GROWTH;
+        }
+        violation = latest;
+    }
+
+    return match best {
+        Some((unit, after)) => Refinement {
+            design: space.design_at(&unit),
+            before,
+            after,
+            evaluations,
+        },
+        None => Refinement {
+            design: design.clone(),
+            before,
+            after: before,
+            evaluations,
+        },
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints;
+
+    // Minimize x^2 + y^2 subject to x + y >= 1, over [0, 2] x [0, 2]. The optimum is x = y = 0.5 on the constraint.
+    #[derive(Clone)]
+    struct Circle([f64; 2]);
+
+    impl Genes for Circle {
+        fn genes(&self) -> Vec<f64> {
+            return self.0.to_vec();
+        }
+
+        fn with_genes(&self, genes: &[f64]) -> Circle {
+            return Circle([genes[0], genes[1]]);
+        }
+
+        fn gene_names(&self) -> Vec<&'static str> {
+            return vec!["x", "y"];
+        }
+    }
+
+    impl Evaluate for Circle {
+        fn evaluation(&self) -> Evaluation {
+            let [x, y] = self.0;
+            return Evaluation {
+                objective: -(x * x + y * y),
+                violation: constraints::below(x + y, 1.0),
+            };
+        }
+    }
+
+    #[test]
+    fn finds_the_constrained_optimum() {
+        let refinement = refine(&Circle([1.5, 1.2]), &[(0.0, 2.0), (0.0, 2.0)]);
+        let [x, y] = refinement.design.0;
+        // The objective is flat along the constraint near the optimum, so the design is looser than its objective.
+        assert!(refinement.after.is_feasible());
+        assert!(x + y < 1.01, "x {}, y {}", x, y);
+        assert!(
+            (x - 0.5).abs() < 0.05 && (y - 0.5).abs() < 0.05,
+            "x {}, y {}",
+            x,
+            y
+        );
+        assert!(refinement.after.objective > -0.505);
+    }
+
+    #[test]
+    fn reaches_an_optimum_on_a_bound() {
+        // Unconstrained, x + y >= 1 holds throughout, so the optimum is the corner nearest the origin of the
+        // shifted box, x = 1 on its lower bound.
+        let refinement = refine(&Circle([1.8, 1.5]), &[(1.0, 2.0), (0.0, 2.0)]);
+        let [x, y] = refinement.design.0;
+        assert_eq!(x, 1.0);
+        assert!(y < 0.01, "y {}", y);
+    }
+}
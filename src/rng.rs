@@ -0,0 +1,54 @@
+use rand::rngs::StdRng;
+use rand::{Error, Rng, RngCore, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+// Reseeds this thread's generator. Each simulation thread does this before building its population.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// Seed for one simulation, mixed from the master seed and the simulation's index (SplitMix64).
+pub fn derive_seed(master: u64, stream: u64) -> u64 {
+    let mut z = master.wrapping_add((stream + 1).wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return z ^ (z >> 31);
+}
+
+// Handle on this thread's seeded generator, used in place of rand::thread_rng.
+#[derive(Copy, Clone)]
+pub struct SimRng;
+
+pub fn thread_rng() -> SimRng {
+    return SimRng;
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        return RNG.with(|rng| rng.borrow_mut().next_u32());
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        return RNG.with(|rng| rng.borrow_mut().next_u64());
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest));
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        return RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest));
+    }
+}
+
+// Standard normal draw from this thread's generator by Box-Muller, 1 - u keeps the logarithm finite.
+pub fn normal() -> f64 {
+    let mut rng = thread_rng();
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    return (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos();
+}
@@ -0,0 +1,278 @@
+use crate::fitness::BeamFitness;
+use crate::rng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rsgenetic::pheno::Phenotype;
+use rsgenetic::sim::select::{Parents, Selector, UnstableMaximizeSelector};
+
+// How parents are picked each generation. Maximize breeds the fittest in pairs, as the simulations always did.
+#[derive(Copy, Clone, Debug)]
+pub enum SelectionStrategy {
+    Maximize,
+    Tournament(usize),
+    Roulette,
+    StochasticUniversal,
+    Rank,
+}
+
+impl Default for SelectionStrategy {
+    fn default() -> SelectionStrategy {
+        return SelectionStrategy::Maximize;
+    }
+}
+
+impl SelectionStrategy {
+    // Accepts maximize, tournament, tournament:<size>, roulette, sus or rank.
+    pub fn parse(name: &str) -> Result<SelectionStrategy, String> {
+        let lower = name.to_lowercase();
+        let mut parts = lower.splitn(2, ':');
+        return match (parts.next(), parts.next()) {
+            (Some("maximize"), None) => Ok(SelectionStrategy::Maximize),
+            (Some("tournament"), None) => Ok(SelectionStrategy::Tournament(3)),
+            (Some("tournament"), Some(size)) => match size.parse::<usize>() {
+                Ok(size) if size > 0 => Ok(SelectionStrategy::Tournament(size)),
+                _ => Err(format!("Bad tournament size {}", size)),
+            },
+            (Some("roulette"), None) => Ok(SelectionStrategy::Roulette),
+            (Some("sus"), None) => Ok(SelectionStrategy::StochasticUniversal),
+            (Some("rank"), None) => Ok(SelectionStrategy::Rank),
+            _ => Err(format!("Unknown selection strategy {}", name)),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            SelectionStrategy::Maximize => String::from("maximize"),
+            SelectionStrategy::Tournament(size) => format!("tournament (size {})", size),
+            SelectionStrategy::Roulette => String::from("roulette"),
+            SelectionStrategy::StochasticUniversal => String::from("stochastic universal sampling"),
+            SelectionStrategy::Rank => String::from("rank"),
+        };
+    }
+
+    // Selector breeding `count` parents, i.e. count / 2 children, per generation.
+    pub fn selector<T>(&self, count: usize) -> Box<dyn Selector<T, BeamFitness>>
+    where
+        T: Phenotype<BeamFitness> + Send + Sync,
+    {
+        return match *self {
+            SelectionStrategy::Maximize => Box::new(UnstableMaximizeSelector::new(count)),
+            SelectionStrategy::Tournament(size) => Box::new(TournamentSelector { count, size }),
+            SelectionStrategy::Roulette => Box::new(WeightedSelector {
+                count,
+                weighting: Weighting::Fitness,
+                universal: false,
+            }),
+            SelectionStrategy::StochasticUniversal => Box::new(WeightedSelector {
+                count,
+                weighting: Weighting::Fitness,
+                universal: true,
+            }),
+            SelectionStrategy::Rank => Box::new(WeightedSelector {
+                count,
+                weighting: Weighting::Rank,
+                universal: false,
+            }),
+        };
+    }
+}
+
+fn check_count(count: usize, population: usize) -> Result<(), String> {
+    if count == 0 || count % 2 != 0 || count > population {
+        return Err(format!(
+            "Invalid parameter `count`: {}. Should be larger than zero, a multiple of two and at most the population size.",
+            count
+        ));
+    }
+    return Ok(());
+}
+
+fn pair_up<'a, T>(population: &'a [T], picks: &[usize]) -> Parents<&'a T> {
+    return picks
+        .chunks(2)
+        .map(|pair| (&population[pair[0]], &population[pair[1]]))
+        .collect();
+}
+
+// Best of `size` individuals drawn at random, once per parent.
+#[derive(Debug)]
+struct TournamentSelector {
+    count: usize,
+    size: usize,
+}
+
+impl<T: Phenotype<BeamFitness>> Selector<T, BeamFitness> for TournamentSelector {
+    fn select<'a>(&self, population: &'a [T]) -> Result<Parents<&'a T>, String> {
+        check_count(self.count, population.len())?;
+        let mut rng = rng::thread_rng();
+        let picks: Vec<usize> = (0..self.count)
+            .map(|_| {
+                (0..self.size)
+                    .map(|_| rng.gen_range(0..population.len()))
+                    .max_by_key(|&index| population[index].fitness())
+                    .unwrap()
+            })
+            .collect();
+        return Ok(pair_up(population, &picks));
+    }
+}
+
+#[derive(Debug)]
+enum Weighting {
+    // Fitness above the worst in the population, plus one so the worst can still be picked.
+    Fitness,
+    // Linear ranking, the worst has weight 1 and the best the population size.
+    Rank,
+}
+
+// Fitness proportionate selection, either a roulette spin per parent or stochastic universal sampling with one spin
+// and evenly spaced pointers.
+#[derive(Debug)]
+struct WeightedSelector {
+    count: usize,
+    weighting: Weighting,
+    universal: bool,
+}
+
+impl<T: Phenotype<BeamFitness>> Selector<T, BeamFitness> for WeightedSelector {
+    fn select<'a>(&self, population: &'a [T]) -> Result<Parents<&'a T>, String> {
+        check_count(self.count, population.len())?;
+        let scores: Vec<BeamFitness> = population.iter().map(|x| x.fitness()).collect();
+        let weights: Vec<f64> = match self.weighting {
+            Weighting::Fitness => {
+                let worst = scores.iter().min().unwrap().value();
+                scores.iter().map(|s| s.value() - worst + 1.0).collect()
+            }
+            Weighting::Rank => {
+                let mut order: Vec<usize> = (0..scores.len()).collect();
+                order.sort_by_key(|&index| scores[index]);
+                let mut weights = vec![0.0; scores.len()];
+                for (rank, index) in order.into_iter().enumerate() {
+                    weights[index] = (rank + 1) as f64;
+                }
+                weights
+            }
+        };
+        let mut cumulative: Vec<f64> = Vec::with_capacity(weights.len());
+        let mut total = 0.0;
+        for weight in &weights {
+            total += weight;
+            cumulative.push(total);
+        }
+
+        let mut rng = rng::thread_rng();
+        let pointers: Vec<f64> = match self.universal {
+            true => {
+                let spacing = total / self.count as f64;
+                let start = rng.gen::<f64>() * spacing;
+                (0..self.count)
+                    .map(|i| start + i as f64 * spacing)
+                    .collect()
+            }
+            false => (0..self.count).map(|_| rng.gen::<f64>() * total).collect(),
+        };
+        let mut picks: Vec<usize> = pointers
+            .iter()
+            .map(|&pointer| {
+                cumulative
+                    .partition_point(|&c| c <= pointer)
+                    .min(population.len() - 1)
+            })
+            .collect();
+        // Universal sampling hands back the picks in population order, shuffle them so pairs are not neighbours.
+        if self.universal {
+            picks.shuffle(&mut rng);
+        }
+        return Ok(pair_up(population, &picks));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct Score(f64);
+
+    impl Phenotype<BeamFitness> for Score {
+        fn fitness(&self) -> BeamFitness {
+            return BeamFitness::new(self.0);
+        }
+
+        fn crossover(&self, other: &Score) -> Score {
+            return Score((self.0 + other.0) / 2.0);
+        }
+
+        fn mutate(&self) -> Score {
+            return self.clone();
+        }
+    }
+
+    // How often each individual is picked as a parent over `rounds` selections.
+    fn counts(
+        strategy: SelectionStrategy,
+        scores: &[f64],
+        count: usize,
+        rounds: usize,
+    ) -> Vec<usize> {
+        rng::seed(1);
+        let population: Vec<Score> = scores.iter().map(|&s| Score(s)).collect();
+        let selector = strategy.selector::<Score>(count);
+        let mut counts = vec![0; population.len()];
+        for _ in 0..rounds {
+            for (a, b) in selector.select(&population).unwrap() {
+                for parent in [a, b] {
+                    counts[population
+                        .iter()
+                        .position(|x| std::ptr::eq(x, parent))
+                        .unwrap()] += 1;
+                }
+            }
+        }
+        return counts;
+    }
+
+    #[test]
+    fn tournaments_favour_the_fitter() {
+        let counts = counts(
+            SelectionStrategy::Tournament(3),
+            &[0.0, 1.0, 2.0, 3.0],
+            4,
+            1000,
+        );
+        // A size 3 tournament picks the worst of four only when all three draws are the worst, 1 in 64.
+        assert!(counts.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(counts[0] < 4000 / 32);
+        assert!(counts[3] > 4000 / 2);
+    }
+
+    #[test]
+    fn universal_sampling_picks_each_within_one_of_its_share() {
+        // Weights are the score above the worst plus one, 22 in all, so each of the 10 pointers covers 2.2.
+        let scores = [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0, 3.0];
+        let counts = counts(SelectionStrategy::StochasticUniversal, &scores, 10, 1);
+        for (score, count) in scores.iter().zip(counts) {
+            let expected = (score + 1.0) / 2.2;
+            assert!(
+                count as f64 >= expected.floor() && count as f64 <= expected.ceil(),
+                "score {} picked {} times, expected {}",
+                score,
+                count,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn rank_selection_ignores_the_size_of_the_gaps() {
+        // Ranks weigh 1 2 3 4 however far ahead the best is, so it gets about 40% of the picks and not all of them.
+        let counts = counts(SelectionStrategy::Rank, &[0.0, 1.0, 2.0, 1.0e9], 4, 1000);
+        let share = counts[3] as f64 / 4000.0;
+        assert!(
+            (0.35..0.45).contains(&share),
+            "best picked {} of the time",
+            share
+        );
+        assert!(counts[0] > 4000 / 20);
+    }
+}
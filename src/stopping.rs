@@ -0,0 +1,135 @@
+use crate::history::GenerationStats;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Optional reasons to end a simulation before it reaches the generation limit.
+#[derive(Clone, Default)]
+pub struct StoppingCriteria {
+    // Generations without any improvement of the best score.
+    pub stagnation: Option<u64>,
+    // Best score improving by less than the delta over the given number of generations.
+    pub fitness_delta: Option<(f64, u64)>,
+    // Generations the best design has to stay unchanged once it meets every requirement.
+    pub feasible_stable: Option<u64>,
+    // Wall-clock time from the start of the run.
+    pub time_budget: Option<Duration>,
+}
+
+#[derive(Copy, Clone)]
+pub enum StopReason {
+    Iterations,
+    Stagnation,
+    FitnessDelta,
+    FeasibleAndStable,
+    TimeBudget,
+}
+
+impl StopReason {
+    pub fn name(&self) -> String {
+        let name = match self {
+            StopReason::Iterations => "generation limit",
+            StopReason::Stagnation => "stagnation",
+            StopReason::FitnessDelta => "fitness delta",
+            StopReason::FeasibleAndStable => "feasible and stable",
+            StopReason::TimeBudget => "time budget",
+        };
+        return String::from(name);
+    }
+}
+
+// Tracks one simulation's statistics against the stopping criteria.
+pub struct EarlyStopper {
+    criteria: StoppingCriteria,
+    started: Instant,
+    best: Option<f64>,
+    unchanged: u64,
+    recent: VecDeque<f64>,
+}
+
+impl EarlyStopper {
+    pub fn new(criteria: StoppingCriteria, started: Instant) -> EarlyStopper {
+        return EarlyStopper {
+            criteria,
+            started,
+            best: None,
+            unchanged: 0,
+            recent: VecDeque::new(),
+        };
+    }
+
+    // Progress towards the criteria as one line of text for a checkpoint: best score, generations unchanged, then
+    // the recent best scores used by the fitness delta.
+    pub fn save(&self) -> String {
+        let mut fields = vec![
+            self.best.map_or(String::from("-"), |b| b.to_string()),
+            self.unchanged.to_string(),
+        ];
+        fields.extend(self.recent.iter().map(|b| b.to_string()));
+        return fields.join(" ");
+    }
+
+    pub fn restore(&mut self, text: &str) -> Result<(), String> {
+        let parse = |field: &str| {
+            field
+                .parse::<f64>()
+                .map_err(|e| format!("Bad stopping state {}: {}", text, e))
+        };
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() < 2 {
+            return Err(format!("Bad stopping state {}", text));
+        }
+        self.best = match fields[0] {
+            "-" => None,
+            best => Some(parse(best)?),
+        };
+        self.unchanged = fields[1]
+            .parse::<u64>()
+            .map_err(|e| format!("Bad stopping state {}: {}", text, e))?;
+        self.recent = fields[2..]
+            .iter()
+            .map(|field| parse(field))
+            .collect::<Result<VecDeque<f64>, String>>()?;
+        return Ok(());
+    }
+
+    pub fn update(&mut self, stats: &GenerationStats) -> Option<StopReason> {
+        match self.best {
+            Some(best) if stats.best <= best => self.unchanged += 1,
+            _ => {
+                self.best = Some(stats.best);
+                self.unchanged = 0;
+            }
+        }
+        if let Some((_, generations)) = self.criteria.fitness_delta {
+            self.recent.push_back(stats.best);
+            if self.recent.len() as u64 > generations + 1 {
+                self.recent.pop_front();
+            }
+        }
+
+        if let Some(budget) = self.criteria.time_budget {
+            if self.started.elapsed() >= budget {
+                return Some(StopReason::TimeBudget);
+            }
+        }
+        if let Some(generations) = self.criteria.stagnation {
+            if self.unchanged >= generations {
+                return Some(StopReason::Stagnation);
+            }
+        }
+        if let Some((delta, generations)) = self.criteria.fitness_delta {
+            if self.recent.len() as u64 > generations {
+                let improvement = self.recent[self.recent.len() - 1] - self.recent[0];
+                if improvement < delta {
+                    return Some(StopReason::FitnessDelta);
+                }
+            }
+        }
+        if let Some(generations) = self.criteria.feasible_stable {
+            if stats.best_feasible && self.unchanged >= generations {
+                return Some(StopReason::FeasibleAndStable);
+            }
+        }
+        return None;
+    }
+}
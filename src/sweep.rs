@@ -0,0 +1,288 @@
+use crate::analysis::{get_deflection, get_factor_of_safety, get_flight_hours};
+use crate::beams::{Beams, Cost, IBeam, Material, RectBeam, Stress, TBeam, Weight};
+use crate::config;
+use crate::constraints::Evaluate;
+use crate::fitness::BeamFitness;
+use crate::ga::Genes;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Grid points per gene when the run gives no --grid for it.
+pub const SWEEP_POINTS: usize = 31;
+// Most designs a sweep evaluates per beam type, over every material. At a few thousand a second this is some minutes
+// and a table of a few hundred megabytes.
+pub const SWEEP_POINTS_MAX: usize = 2_000_000;
+
+// Evenly spaced values of one gene, both ends included.
+#[derive(Clone)]
+pub struct Axis {
+    pub gene: String,
+    pub min: f64,
+    pub max: f64,
+    pub points: usize,
+}
+
+impl Axis {
+    // Accepts <gene>=<min>:<max>:<points>.
+    pub fn parse(text: &str) -> Result<Axis, String> {
+        let error = || format!("Bad grid {}, expected <gene>=<min>:<max>:<points>", text);
+        let (gene, range) = text.split_at(text.find('=').ok_or_else(error)?);
+        let parts: Vec<&str> = range[1..].split(':').collect();
+        if parts.len() != 3 {
+            return Err(error());
+        }
+        let min = parts[0].parse::<f64>().map_err(|_| error())?;
+        let max = parts[1].parse::<f64>().map_err(|_| error())?;
+        let points = parts[2].parse::<usize>().map_err(|_| error())?;
+        if points == 0 || min > max || (points == 1 && min != max) {
+            return Err(error());
+        }
+        return Ok(Axis {
+            gene: gene.to_lowercase(),
+            min,
+            max,
+            points,
+        });
+    }
+
+    fn values(&self) -> Vec<f64> {
+        if self.points == 1 {
+            return vec![self.min];
+        }
+        let step = (self.max - self.min) / (self.points - 1) as f64;
+        return (0..self.points)
+            .map(|i| self.min + i as f64 * step)
+            .collect();
+    }
+}
+
+// Every combination of gene values. A gene takes its --grid axis if there is one, otherwise SWEEP_POINTS over
+// its bounds, or its single value when the bounds fix it. Points are made one at a time, the last gene varying
+// fastest.
+pub struct Grid {
+    values: Vec<Vec<f64>>,
+}
+
+impl Grid {
+    pub fn new(names: &[&str], bounds: &[(f64, f64)], axes: &[Axis]) -> Grid {
+        let values = names
+            .iter()
+            .zip(bounds)
+            .map(|(name, &(min, max))| {
+                let axis = match axes.iter().find(|axis| axis.gene == *name) {
+                    Some(axis) => axis.clone(),
+                    None => Axis {
+                        gene: name.to_string(),
+                        min,
+                        max,
+                        points: match min == max {
+                            true => 1,
+                            false => SWEEP_POINTS,
+                        },
+                    },
+                };
+                axis.values()
+            })
+            .collect();
+        return Grid { values };
+    }
+
+    // Number of points, saturating rather than overflowing for absurd grids.
+    pub fn size(&self) -> usize {
+        return self
+            .values
+            .iter()
+            .fold(1, |size: usize, values| size.saturating_mul(values.len()));
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Vec<f64>> + '_ {
+        return (0..self.size()).map(move |index| {
+            let mut rest = index;
+            let mut point = vec![0.0; self.values.len()];
+            for (gene, values) in self.values.iter().enumerate().rev() {
+                point[gene] = values[rest % values.len()];
+                rest /= values.len();
+            }
+            point
+        });
+    }
+}
+
+// A CSV written a row at a time, so a sweep never holds its table in memory.
+pub struct Table {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Table {
+    pub fn create(path: &Path, header: &[String]) -> Result<Table, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Cannot write sweep table {}: {}", path.display(), e))?;
+        let mut table = Table {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        };
+        table.row(&header.join(","))?;
+        return Ok(table);
+    }
+
+    pub fn row(&mut self, row: &str) -> Result<(), String> {
+        return writeln!(self.writer, "{}", row)
+            .map_err(|e| format!("Cannot write sweep table {}: {}", self.path.display(), e));
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        return self
+            .writer
+            .flush()
+            .map_err(|e| format!("Cannot write sweep table {}: {}", self.path.display(), e));
+    }
+}
+
+// Evaluates one beam type in each material at every point of the grid, writing the table to `<dir>/<shape>.csv`
+// a row at a time and printing the best feasible point per material. Returns the best feasible design of the type.
+fn sweep_beam<T>(
+    dir: &str,
+    shape: &str,
+    grid: &Grid,
+    prototypes: &[(Material, T)],
+) -> Result<Option<T>, String>
+where
+    T: Genes + Evaluate + Stress + Weight + Cost + Copy,
+{
+    let names = prototypes[0].1.gene_names();
+    let mut header: Vec<String> = vec![String::from("material")];
+    header.extend(names.iter().map(|name| name.to_string()));
+    header.extend(
+        [
+            "weight",
+            "cost",
+            "fos",
+            "deflection",
+            "flight_hours",
+            "objective",
+            "violation",
+            "feasible",
+            "score",
+        ]
+        .iter()
+        .map(|column| column.to_string()),
+    );
+
+    let mut table = Table::create(&Path::new(dir).join(format!("{}.csv", shape)), &header)?;
+    let mut best: Option<(BeamFitness, T)> = None;
+    for &(material, prototype) in prototypes {
+        let mut material_best: Option<(BeamFitness, T)> = None;
+        for point in grid.points() {
+            let beam = prototype.with_genes(&point);
+            let evaluation = beam.evaluation();
+            let score = BeamFitness::new(config::get().constraint_handling.fitness(evaluation));
+            let mut row: Vec<String> = vec![format!("{:?}", material)];
+            row.extend(point.iter().map(|gene| gene.to_string()));
+            row.extend(
+                [
+                    beam.weight(),
+                    beam.cost(),
+                    get_factor_of_safety(&beam),
+                    get_deflection(&beam),
+                    get_flight_hours(&beam),
+                    evaluation.objective,
+                    evaluation.violation,
+                ]
+                .iter()
+                .map(|output| output.to_string()),
+            );
+            row.push(evaluation.is_feasible().to_string());
+            row.push(score.to_string());
+            table.row(&row.join(","))?;
+            if evaluation.is_feasible() && material_best.map_or(true, |(b, _)| score > b) {
+                material_best = Some((score, beam));
+            }
+        }
+        match material_best {
+            Some((score, beam)) => {
+                let genes: Vec<String> = names
+                    .iter()
+                    .zip(beam.genes())
+                    .map(|(name, gene)| format!("{} {}", name, gene))
+                    .collect();
+                println!(
+                    " {} {:?}: best feasible score {} at {}",
+                    shape,
+                    material,
+                    score,
+                    genes.join(", ")
+                );
+                if best.map_or(true, |(b, _)| score > b) {
+                    best = material_best;
+                }
+            }
+            None => println!(" {} {:?}: no feasible point", shape, material),
+        }
+    }
+    table.finish()?;
+    return Ok(best.map(|(_, beam)| beam));
+}
+
+// Grid search over every beam type and material, writing a table per type to `dir` and returning the best feasible
+// design. `bounds` gives the gene bounds of a beam. Grids naming a gene no beam type has, or over SWEEP_POINTS_MAX
+// designs for any type, are refused before anything is evaluated.
+pub fn run(
+    dir: &str,
+    rects: &[(Material, RectBeam)],
+    ts: &[(Material, TBeam)],
+    is: &[(Material, IBeam)],
+    bounds: &dyn Fn(&Beams) -> Vec<(f64, f64)>,
+) -> Result<Option<Beams>, String> {
+    let types = [
+        (Beams::R(rects[0].1), rects.len()),
+        (Beams::T(ts[0].1), ts.len()),
+        (Beams::I(is[0].1), is.len()),
+    ];
+    let mut known: Vec<&str> = Vec::new();
+    for name in types.iter().flat_map(|(beam, _)| beam.gene_names()) {
+        if !known.contains(&name) {
+            known.push(name);
+        }
+    }
+    for axis in &config::get().grid {
+        if !known.contains(&axis.gene.as_str()) {
+            return Err(format!(
+                "Unknown gene {} in --grid, expected one of {}",
+                axis.gene,
+                known.join(", ")
+            ));
+        }
+    }
+
+    let grids: Vec<Grid> = types
+        .iter()
+        .map(|(beam, _)| Grid::new(&beam.gene_names(), &bounds(beam), &config::get().grid))
+        .collect();
+    for ((beam, materials), grid) in types.iter().zip(&grids) {
+        let designs = grid.size().saturating_mul(*materials);
+        if designs > SWEEP_POINTS_MAX {
+            return Err(format!(
+                "The {} sweep has {} points over {} materials, {} designs, more than the {} a sweep evaluates. \
+                 Use --grid to take fewer points over some genes.",
+                beam.shape(),
+                grid.size(),
+                materials,
+                designs,
+                SWEEP_POINTS_MAX
+            ));
+        }
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Cannot create sweep directory {}: {}", dir, e))?;
+
+    println!("Sweep");
+    let best = [
+        sweep_beam(dir, "rect", &grids[0], rects)?.map(Beams::R),
+        sweep_beam(dir, "t", &grids[1], ts)?.map(Beams::T),
+        sweep_beam(dir, "i", &grids[2], is)?.map(Beams::I),
+    ];
+    return Ok(best.iter().flatten().copied().max_by_key(|beam| {
+        BeamFitness::new(config::get().constraint_handling.fitness(beam.evaluation()))
+    }));
+}